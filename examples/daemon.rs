@@ -14,7 +14,7 @@ use command_group::AsyncCommandGroup;
 #[tokio::main]
 async fn main() {
 	tokio::process::Command::new("python3")
-		.args(&["-m", "http.server", "8000"])
+		.args(["-m", "http.server", "8000"])
 		.stderr(Stdio::null())
 		.stdout(Stdio::null())
 		.group_spawn()
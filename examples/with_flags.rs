@@ -11,7 +11,7 @@ use winapi::um::winbase::CREATE_NO_WINDOW;
 #[tokio::main]
 async fn main() {
 	let group = tokio::process::Command::new("python3")
-		.args(&["-m", "http.server", "8000"])
+		.args(["-m", "http.server", "8000"])
 		.stderr(Stdio::null())
 		.stdout(Stdio::null())
 		.group()
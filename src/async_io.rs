@@ -0,0 +1,105 @@
+//! Executor-agnostic asynchronous process groups, built on [async-io](https://docs.rs/async-io).
+//!
+//! The group is spawned from the standard library’s [`Command`] exactly like with
+//! [`CommandGroup`](crate::CommandGroup), and only waiting on it is asynchronous. This uses
+//! async-io’s reactor, which runs on its own thread when needed, so the futures can be awaited
+//! from any executor: smol, async-std, Tokio, or a plain `block_on`.
+
+use std::process::{Command, ExitStatus};
+
+use crate::{builder::CommandGroupBuilder, AsyncIoGroupChild, Result};
+
+pub(crate) mod child;
+
+/// Extensions for [`Command`] adding support for process groups which can be waited on
+/// asynchronously, with any executor.
+///
+/// The methods are suffixed with `_async` so they don't clash with those of
+/// [`CommandGroup`](crate::CommandGroup), which is implemented on the same type. To set group
+/// options, use [`CommandGroup::group()`](crate::CommandGroup::group) and then
+/// [`spawn_async()`](CommandGroupBuilder::spawn_async).
+// the only implementor is Command, so the Send-ness of the futures is always known to callers
+#[allow(async_fn_in_trait)]
+pub trait AsyncIoCommandGroup {
+	/// Executes the command as a child process group, returning a handle to it.
+	///
+	/// By default, stdin, stdout and stderr are inherited from the parent.
+	///
+	/// On Windows, this creates a job object instead of a POSIX process group.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::AsyncIoCommandGroup;
+	///
+	/// Command::new("ls")
+	///         .group_spawn_async()
+	///         .expect("ls command failed to start");
+	/// ```
+	fn group_spawn_async(&mut self) -> Result<AsyncIoGroupChild>;
+
+	/// Executes a command as a child process group, waiting for it to finish and
+	/// collecting its status.
+	///
+	/// By default, stdin, stdout and stderr are inherited from the parent.
+	///
+	/// On Windows, this creates a job object instead of a POSIX process group.
+	///
+	/// # Examples
+	///
+	/// ```should_panic
+	/// # async_io::block_on(async {
+	/// use std::process::Command;
+	/// use command_group::AsyncIoCommandGroup;
+	///
+	/// let status = Command::new("/bin/cat")
+	///                      .arg("file.txt")
+	///                      .group_status_async()
+	///                      .await
+	///                      .expect("failed to execute process");
+	///
+	/// println!("process finished with: {}", status);
+	///
+	/// assert!(status.success());
+	/// # });
+	/// ```
+	async fn group_status_async(&mut self) -> Result<ExitStatus> {
+		let mut child = self.group_spawn_async()?;
+		child.wait().await
+	}
+}
+
+impl AsyncIoCommandGroup for Command {
+	fn group_spawn_async(&mut self) -> Result<AsyncIoGroupChild> {
+		CommandGroupBuilder::new(self).spawn_async()
+	}
+}
+
+impl CommandGroupBuilder<'_, Command> {
+	/// Executes the command as a child process group, returning a handle which can be waited on
+	/// asynchronously.
+	///
+	/// This sets up the group in the same way as [`spawn()`](Self::spawn), so all of the builder's
+	/// options apply.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// Command::new("ls")
+	///         .group()
+	///         .kill_on_drop(true)
+	///         .spawn_async()
+	///         .expect("ls command failed to start");
+	/// ```
+	pub fn spawn_async(&mut self) -> Result<AsyncIoGroupChild> {
+		self.spawn().map(AsyncIoGroupChild::new)
+	}
+}
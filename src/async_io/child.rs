@@ -0,0 +1,318 @@
+use std::{
+	fmt,
+	future::poll_fn,
+	future::Future,
+	pin::Pin,
+	process::{Child, ExitStatus},
+	task::Poll,
+	time::{Duration, Instant},
+};
+
+use async_io::Timer;
+
+use crate::{error::ignore_exited, GroupChild, GroupKill, Result};
+
+#[cfg(unix)]
+use unix::LeaderExit;
+#[cfg(windows)]
+use windows::LeaderExit;
+
+#[cfg(unix)]
+use nix::sys::signal::Signal;
+
+#[cfg(unix)]
+mod unix;
+#[cfg(windows)]
+mod windows;
+
+/// Representation of a running or exited child process group, which can be waited on with any
+/// executor.
+///
+/// This wraps a [`GroupChild`], and waits for it asynchronously using
+/// [async-io](https://docs.rs/async-io)’s reactor. Where the platform supports it, this sleeps
+/// until the group leader exits: with a pidfd on Linux 5.3 and later, and with the process handle
+/// on Windows. The rest of the group, or the leader on other platforms, is polled for at a short
+/// interval with an async-io timer.
+///
+/// # Examples
+///
+/// ```should_panic
+/// # async_io::block_on(async {
+/// use std::process::Command;
+/// use command_group::AsyncIoCommandGroup;
+///
+/// let mut child = Command::new("/bin/cat")
+///                         .arg("file.txt")
+///                         .group_spawn_async()
+///                         .expect("failed to execute child");
+///
+/// let ecode = child.wait()
+///                  .await
+///                  .expect("failed to wait on child");
+///
+/// assert!(ecode.success());
+/// # });
+/// ```
+pub struct AsyncIoGroupChild {
+	inner: GroupChild,
+	leader: LeaderExit,
+	exitstatus: Option<ExitStatus>,
+}
+
+impl fmt::Debug for AsyncIoGroupChild {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("AsyncIoGroupChild")
+			.field("inner", &self.inner)
+			.field("exit_status", &self.exitstatus)
+			.finish()
+	}
+}
+
+impl AsyncIoGroupChild {
+	pub(crate) fn new(mut inner: GroupChild) -> Self {
+		Self {
+			leader: LeaderExit::new(inner.inner()),
+			inner,
+			exitstatus: None,
+		}
+	}
+
+	/// Returns the stdlib [`Child`] object.
+	///
+	/// Its stdio handles are the standard library’s blocking ones. On Unix, they can be made
+	/// asynchronous by wrapping them in [`async_io::Async`].
+	///
+	/// Note that the inner child may not be in the same state as this output child, due to how
+	/// methods like `wait` and `kill` are implemented. It is not recommended to use this method
+	/// _after_ using any of the other methods on this struct.
+	pub fn inner(&mut self) -> &mut Child {
+		self.inner.inner()
+	}
+
+	/// Consumes itself and returns the stdlib [`Child`] object.
+	///
+	/// This behaves like [`GroupChild::into_inner()`].
+	pub fn into_inner(self) -> Child {
+		self.inner.into_inner()
+	}
+
+	/// Lets the group carry on running on its own, and drops this handle to it.
+	///
+	/// This behaves like [`GroupChild::detach()`].
+	pub fn detach(self) {
+		self.inner.detach();
+	}
+
+	/// Forces the child process group to exit, and waits for it to do so.
+	///
+	/// This is equivalent to sending a SIGKILL on Unix platforms.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # async_io::block_on(async {
+	/// use std::process::Command;
+	/// use command_group::AsyncIoCommandGroup;
+	///
+	/// let mut command = Command::new("yes");
+	/// if let Ok(mut child) = command.group_spawn_async() {
+	///     child.kill().await.expect("command wasn't running");
+	/// } else {
+	///     println!("yes command didn't start");
+	/// }
+	/// # });
+	/// ```
+	pub async fn kill(&mut self) -> Result<()> {
+		self.start_kill()?;
+		self.wait().await?;
+		Ok(())
+	}
+
+	/// Attempts to force the child to exit, but does not wait for the request to take effect.
+	///
+	/// This is equivalent to sending a SIGKILL on Unix platforms.
+	///
+	/// Note that on Unix platforms it is possible for a zombie process to remain after a kill is
+	/// sent; to avoid this, the caller should ensure that either `child.wait().await` or
+	/// `child.try_wait()` is invoked successfully.
+	pub fn start_kill(&mut self) -> Result<()> {
+		self.inner.kill()
+	}
+
+	/// Asks the child process group to exit, without forcing it.
+	///
+	/// This behaves like [`GroupChild::terminate()`].
+	pub fn terminate(&mut self) -> Result<()> {
+		self.inner.terminate()
+	}
+
+	/// Returns a handle which can kill the group without owning the child.
+	///
+	/// This behaves like [`GroupChild::kill_handle()`].
+	pub fn kill_handle(&self) -> Result<GroupKill> {
+		self.inner.kill_handle()
+	}
+
+	/// Returns the OS-assigned process group identifier.
+	///
+	/// This behaves like [`GroupChild::id()`].
+	pub fn id(&self) -> u32 {
+		self.inner.id()
+	}
+
+	/// Returns the OS-assigned process identifier of the child, or `None` once it has been reaped.
+	///
+	/// This behaves like [`GroupChild::id_checked()`].
+	pub fn id_checked(&self) -> Option<u32> {
+		if self.exitstatus.is_some() {
+			None
+		} else {
+			self.inner.id_checked()
+		}
+	}
+
+	/// Returns the process group ID.
+	///
+	/// This behaves like [`GroupChild::group_id()`].
+	pub fn group_id(&self) -> u32 {
+		self.inner.group_id()
+	}
+
+	/// Waits for the child group to exit completely, returning the status that the process leader
+	/// exited with.
+	///
+	/// Like [`GroupChild::wait()`], this closes the child's stdin first, and the status is cached
+	/// once collected.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # async_io::block_on(async {
+	/// use std::process::Command;
+	/// use command_group::AsyncIoCommandGroup;
+	///
+	/// let mut command = Command::new("ls");
+	/// if let Ok(mut child) = command.group_spawn_async() {
+	///     child.wait().await.expect("command wasn't running");
+	///     println!("Child has finished its execution!");
+	/// } else {
+	///     println!("ls command didn't start");
+	/// }
+	/// # });
+	/// ```
+	pub async fn wait(&mut self) -> Result<ExitStatus> {
+		if let Some(es) = self.exitstatus {
+			return Ok(es);
+		}
+
+		drop(self.inner.inner().stdin.take());
+		let status = self.wait_until(None).await?.expect("no deadline to miss");
+		self.exitstatus = Some(status);
+		Ok(status)
+	}
+
+	/// Attempts to collect the exit status of the child if it has already exited.
+	///
+	/// This behaves like [`GroupChild::try_wait()`]: it returns the status as soon as the leader
+	/// has exited, and reaps what it can of the rest of the group.
+	pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		if self.exitstatus.is_some() {
+			return Ok(self.exitstatus);
+		}
+
+		self.inner.try_wait()
+	}
+
+	/// Waits for the child group to exit for up to `timeout`, killing it if it hasn't by then.
+	///
+	/// Returns `Ok(Some(status))` with the status that the process leader exited with if the group
+	/// finished in time, or `Ok(None)` if it had to be [`kill()`](Self::kill)ed. In that case, the
+	/// group has been waited on before returning, and its status is available from
+	/// [`wait()`](Self::wait).
+	///
+	/// Like `wait`, this closes the child's stdin first.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # async_io::block_on(async {
+	/// use std::{process::Command, time::Duration};
+	/// use command_group::AsyncIoCommandGroup;
+	///
+	/// let mut child = Command::new("make").arg("test").group_spawn_async().unwrap();
+	/// match child.wait_or_kill(Duration::from_secs(60)).await.expect("failed to wait for command") {
+	///     Some(status) => println!("exited with: {}", status),
+	///     None => println!("timed out"),
+	/// }
+	/// # });
+	/// ```
+	pub async fn wait_or_kill(&mut self, timeout: Duration) -> Result<Option<ExitStatus>> {
+		if let Some(es) = self.exitstatus {
+			return Ok(Some(es));
+		}
+
+		drop(self.inner.inner().stdin.take());
+		if let Some(status) = self.wait_until(Some(Instant::now() + timeout)).await? {
+			self.exitstatus = Some(status);
+			return Ok(Some(status));
+		}
+
+		ignore_exited(self.start_kill())?;
+		self.wait().await?;
+		Ok(None)
+	}
+
+	/// Waits for the whole group to exit, or for the deadline to pass.
+	async fn wait_until(&mut self, deadline: Option<Instant>) -> Result<Option<ExitStatus>> {
+		const MAX_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+		let mut interval = Duration::from_millis(1);
+		loop {
+			if let Some(status) = self.inner.try_wait_group()? {
+				return Ok(Some(status));
+			}
+
+			let remaining =
+				deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+			if remaining.is_some_and(|remaining| remaining.is_zero()) {
+				return Ok(None);
+			}
+
+			// Sleep until the leader exits if that can be waited on, otherwise (and then for the
+			// rest of the group) poll with a backoff.
+			let timeout = if self.leader.is_armed() {
+				remaining
+			} else {
+				let timeout = remaining.map_or(interval, |remaining| remaining.min(interval));
+				interval = (interval * 2).min(MAX_POLL_INTERVAL);
+				Some(timeout)
+			};
+
+			let mut timer = timeout.map(Timer::after);
+			poll_fn(|cx| {
+				if self.leader.poll_exited(cx).is_ready() {
+					return Poll::Ready(());
+				}
+
+				match &mut timer {
+					Some(timer) => Pin::new(timer).poll(cx).map(drop),
+					None => Poll::Pending,
+				}
+			})
+			.await;
+		}
+	}
+}
+
+#[cfg(unix)]
+impl crate::UnixChildExt for AsyncIoGroupChild {
+	fn signal(&self, sig: Signal) -> Result<()> {
+		self.inner.signal(sig)
+	}
+}
@@ -0,0 +1,55 @@
+use std::{
+	process::Child,
+	task::{Context, Poll},
+};
+
+#[cfg(target_os = "linux")]
+use async_io::Async;
+#[cfg(target_os = "linux")]
+use std::os::fd::OwnedFd;
+
+/// Becomes ready once, when the group leader exits, if the platform lets us wait for that.
+///
+/// On Linux 5.3 and later, this registers a pidfd for the leader with the reactor. Elsewhere, it
+/// is never armed, and the leader has to be polled for.
+pub(super) struct LeaderExit {
+	#[cfg(target_os = "linux")]
+	fd: Option<Async<OwnedFd>>,
+}
+
+impl LeaderExit {
+	#[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+	pub fn new(leader: &Child) -> Self {
+		Self {
+			#[cfg(target_os = "linux")]
+			fd: crate::pidfd::pidfd_open(leader.id()).and_then(|fd| Async::new(fd).ok()),
+		}
+	}
+
+	/// Whether this will become ready when the leader exits.
+	pub fn is_armed(&self) -> bool {
+		#[cfg(target_os = "linux")]
+		return self.fd.is_some();
+
+		#[cfg(not(target_os = "linux"))]
+		return false;
+	}
+
+	/// Polls for the leader's exit, disarming once it's been seen.
+	///
+	/// A pidfd stays readable once its process has exited, so this only returns ready once; after
+	/// that, or if it was never armed, it is always pending.
+	#[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+	pub fn poll_exited(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+		#[cfg(target_os = "linux")]
+		if let Some(fd) = &self.fd {
+			// on error, fall back to polling for the leader
+			if fd.poll_readable(cx).is_ready() {
+				self.fd = None;
+				return Poll::Ready(());
+			}
+		}
+
+		Poll::Pending
+	}
+}
@@ -0,0 +1,47 @@
+use std::{
+	os::windows::io::{AsHandle, OwnedHandle},
+	process::Child,
+	task::{Context, Poll},
+};
+
+use async_io::os::windows::Waitable;
+
+/// Becomes ready once, when the group leader exits.
+///
+/// This waits on a duplicate of the leader's process handle, which is signaled when it exits.
+pub(super) struct LeaderExit {
+	handle: Option<Waitable<OwnedHandle>>,
+}
+
+impl LeaderExit {
+	pub fn new(leader: &Child) -> Self {
+		Self {
+			handle: leader
+				.as_handle()
+				.try_clone_to_owned()
+				.ok()
+				.and_then(|handle| Waitable::new(handle).ok()),
+		}
+	}
+
+	/// Whether this will become ready when the leader exits.
+	pub fn is_armed(&self) -> bool {
+		self.handle.is_some()
+	}
+
+	/// Polls for the leader's exit, disarming once it's been seen.
+	///
+	/// A process handle stays signaled once it has exited, so this only returns ready once; after
+	/// that, or if it couldn't be armed, it is always pending.
+	pub fn poll_exited(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+		if let Some(handle) = &self.handle {
+			// on error, fall back to polling for the leader
+			if handle.poll_ready(cx).is_ready() {
+				self.handle = None;
+				return Poll::Ready(());
+			}
+		}
+
+		Poll::Pending
+	}
+}
@@ -1,37 +1,876 @@
-//!
+//! Builder for configuring process groups before they are spawned.
+
+#[cfg(unix)]
+use nix::sys::resource::Resource;
+#[cfg(unix)]
+use std::{cell::Cell, marker::PhantomData, ptr};
+#[cfg(target_os = "linux")]
+use std::{
+	ffi::{CStr, CString},
+	os::unix::ffi::OsStringExt,
+	path::{Path, PathBuf},
+};
+use std::{
+	ops::{Deref, DerefMut},
+	process::Stdio,
+};
 
 /// CommandGroupBuilder is a builder for a group of processes.
 ///
 /// It is created via the `group` method on [`Command`](std::process::Command) or
-/// [`AsyncCommand`](tokio::process::Command).
+/// [`AsyncCommand`](tokio::process::Command), which borrows the command, or via `into_group`, which
+/// moves it into the builder. An owned builder (with a `'static` lifetime) can be stored and
+/// spawned from after the original `Command` expression has gone.
 pub struct CommandGroupBuilder<'a, T> {
-	pub(crate) command: &'a mut T,
-	#[allow(dead_code)]
+	pub(crate) command: BuilderCommand<'a, T>,
 	pub(crate) kill_on_drop: bool,
+	#[cfg(unix)]
+	pub(crate) kill_on_drop_signal: crate::Signal,
+	#[cfg_attr(windows, allow(dead_code))]
+	pub(crate) new_session: bool,
+	#[cfg(unix)]
+	pub(crate) process_group: i32,
+	#[cfg(unix)]
+	pub(crate) foreground: bool,
+	#[cfg(unix)]
+	rlimits: Vec<(Resource, u64, u64)>,
+	#[cfg(unix)]
+	nice: i32,
+	#[cfg(target_os = "linux")]
+	cgroup: Option<PathBuf>,
+	pub(crate) merge_stderr: bool,
+	#[cfg(unix)]
+	setup_hook: bool,
+	#[cfg(unix)]
+	pub(crate) sets_process_group: bool,
+	#[cfg(windows)]
+	pub(crate) new_process_group: bool,
+	#[cfg(windows)]
+	pub(crate) allow_breakaway: bool,
+	#[cfg(windows)]
+	priority_class: Option<PriorityClass>,
+	#[cfg(windows)]
+	new_console: bool,
+	#[cfg(windows)]
+	no_window: bool,
+	#[cfg(windows)]
+	pub(crate) job_limits: crate::winres::JobLimits,
 	#[allow(dead_code)]
 	pub(crate) creation_flags: u32,
 }
 
+impl<T> CommandGroupBuilder<'static, T> {
+	pub(crate) fn owned(command: T) -> Self {
+		Self::with_command(BuilderCommand::Owned(command))
+	}
+}
+
 impl<'a, T> CommandGroupBuilder<'a, T> {
 	pub(crate) fn new(command: &'a mut T) -> Self {
+		Self::with_command(BuilderCommand::Borrowed(command))
+	}
+
+	fn with_command(command: BuilderCommand<'a, T>) -> Self {
 		Self {
 			command,
 			kill_on_drop: false,
+			#[cfg(unix)]
+			kill_on_drop_signal: crate::Signal::SIGKILL,
+			new_session: false,
+			#[cfg(unix)]
+			process_group: 0,
+			#[cfg(unix)]
+			foreground: false,
+			#[cfg(unix)]
+			rlimits: Vec::new(),
+			#[cfg(unix)]
+			nice: 0,
+			#[cfg(target_os = "linux")]
+			cgroup: None,
+			merge_stderr: false,
+			#[cfg(unix)]
+			setup_hook: false,
+			#[cfg(unix)]
+			sets_process_group: false,
+			#[cfg(windows)]
+			new_process_group: false,
+			#[cfg(windows)]
+			allow_breakaway: false,
+			#[cfg(windows)]
+			priority_class: None,
+			#[cfg(windows)]
+			new_console: false,
+			#[cfg(windows)]
+			no_window: false,
+			#[cfg(windows)]
+			job_limits: Default::default(),
 			creation_flags: 0,
 		}
 	}
 
-	/// See [`tokio::process::Command::kill_on_drop`].
-	#[cfg(any(windows, feature = "with-tokio"))]
+	/// Returns a reference to the command the group will be spawned from.
+	pub fn get_command(&self) -> &T {
+		&self.command
+	}
+
+	/// Returns a mutable reference to the command the group will be spawned from.
+	///
+	/// This can be used to make last-minute changes to it, like adding arguments or environment
+	/// variables, without having to go back to the `Command`.
+	pub fn get_command_mut(&mut self) -> &mut T {
+		&mut self.command
+	}
+
+	/// Sets whether the process group should be killed when its handle is dropped.
+	///
+	/// On Unix, this sends SIGKILL to the whole group when the group child is dropped, or the
+	/// signal set with `kill_on_drop_signal()`. Nothing is sent if the group leader has already
+	/// been waited on, as its ID may then have been reused. To let the group run on after all, use
+	/// the child's `into_inner()` method.
+	///
+	/// Dropping doesn't wait for the group to exit. The std child reaps those of its processes
+	/// which have already exited, and the others linger as zombies until this process exits, as
+	/// with std's `Child`. Tokio reaps dropped children in the background on its own.
+	///
+	/// On Windows, this configures the job object to terminate all of its processes once the last
+	/// handle to it is closed. As job termination is not signal-based, the signal can't be chosen.
+	///
+	/// This is off by default. See also [`tokio::process::Command::kill_on_drop`].
 	pub fn kill_on_drop(&mut self, kill_on_drop: bool) -> &mut Self {
 		self.kill_on_drop = kill_on_drop;
 		self
 	}
 
+	/// Sets the signal sent to the process group when its handle is dropped, and enables
+	/// [`kill_on_drop`](Self::kill_on_drop).
+	///
+	/// For example, SIGTERM lets processes flush their state before exiting, although they may then
+	/// also choose not to exit at all. Defaults to SIGKILL.
+	#[cfg(unix)]
+	pub fn kill_on_drop_signal(&mut self, signal: crate::Signal) -> &mut Self {
+		self.kill_on_drop = true;
+		self.kill_on_drop_signal = signal;
+		self
+	}
+
+	/// Sets whether the process should be started in a new session.
+	///
+	/// On Unix, this calls `setsid()` in the child before it executes the command, instead of only
+	/// putting it in a new process group. The child then leads both a new session and a new
+	/// process group, whose ID is the child's own process ID, so signalling and waiting on the
+	/// group work as usual. This takes precedence over `process_group`.
+	///
+	/// A new session has no controlling terminal: the child is detached from the parent's TTY, so
+	/// it won't receive signals from it (like SIGHUP or SIGINT), and may not read from it. This is
+	/// usually what daemons want.
+	///
+	/// A process group leader can't create a new session. If the command was already made to lead
+	/// its own group some other way, for example with [`CommandExt::process_group`] directly, or
+	/// by spawning it as a group from another builder, the child stays in that group without a
+	/// new session. Any other failure aborts the spawn.
+	///
+	/// On Windows, this does nothing: job objects are used regardless.
+	///
+	/// [`CommandExt::process_group`]: https://doc.rust-lang.org/std/os/unix/process/trait.CommandExt.html#tymethod.process_group
+	///
+	/// This is off by default.
+	pub fn new_session(&mut self, new_session: bool) -> &mut Self {
+		self.new_session = new_session;
+		self
+	}
+
+	/// Sets the process group the process should join, instead of creating a new one.
+	///
+	/// When this is non-zero, the child joins the existing process group with this ID, for example
+	/// to signal several commands together. Signalling and killing the child then affects that
+	/// whole group, but waiting on it only waits for the child itself: other processes in the
+	/// group are left alone. If the group doesn't exist, spawning fails.
+	///
+	/// Zero, the default, puts the child in a new group which it leads. This is ignored if
+	/// [`new_session`](Self::new_session) is set, as a new session always has a new group.
+	#[cfg(unix)]
+	pub fn process_group(&mut self, pgid: i32) -> &mut Self {
+		self.process_group = pgid;
+		self
+	}
+
+	/// Sets whether the process group should be made the foreground group of the terminal.
+	///
+	/// A process group which isn't in the foreground of its controlling terminal is stopped with
+	/// `SIGTTIN` when it tries to read from it, and doesn't receive the signals sent by keys like
+	/// Ctrl-C. Interactive programs, like editors or shells, must therefore be in the foreground.
+	///
+	/// This calls `tcsetpgrp()` in the child before it executes the command, so it's in the
+	/// foreground from the start. That takes the foreground away from our own group: once the
+	/// child is done, a shell would give it back with `tcsetpgrp()` and its own group ID. To do
+	/// this after spawning instead, see [`GroupChild::set_foreground()`].
+	///
+	/// This requires a controlling terminal, and so fails to spawn if there's none, and when
+	/// combined with [`new_session`](Self::new_session), which detaches the child from it.
+	///
+	/// This is off by default.
+	///
+	/// [`GroupChild::set_foreground()`]: crate::GroupChild::set_foreground
+	#[cfg(unix)]
+	pub fn foreground(&mut self, foreground: bool) -> &mut Self {
+		self.foreground = foreground;
+		self
+	}
+
+	/// Sets a resource limit for the process, like `ulimit` in a shell.
+	///
+	/// This calls `setrlimit()` in the child before it executes the command, with the given soft
+	/// and hard limits. Limits apply to each process on its own, not to the group as a whole, and
+	/// are inherited by the processes the child starts, so they cap every process in the group
+	/// individually. Use [`RLIM_INFINITY`](nix::libc::RLIM_INFINITY) for no limit.
+	///
+	/// This can be called several times to limit several resources; calling it again for the same
+	/// resource replaces its limits. An unprivileged process can only lower its hard limits, and
+	/// the soft limit can't be above the hard limit: if the limits can't be set, spawning fails.
+	///
+	/// The limits are applied between fork and exec, where only async-signal-safe functions may be
+	/// called. Setting them only calls `setrlimit()`, so this is safe even in a multithreaded
+	/// program.
+	///
+	/// By default, the process inherits our own limits.
+	#[cfg(unix)]
+	pub fn rlimit(&mut self, resource: Resource, soft: u64, hard: u64) -> &mut Self {
+		self.rlimits.retain(|(other, _, _)| *other != resource);
+		self.rlimits.push((resource, soft, hard));
+		self
+	}
+
+	/// Lowers (or raises) the scheduling priority of the process, like `nice` in a shell.
+	///
+	/// The value is an increment to the niceness the process would otherwise start with, that is
+	/// our own, and not an absolute priority: positive values make the process nicer to others, so
+	/// it gets less CPU time when the system is busy. Processes started by the child inherit its
+	/// niceness, so this applies to the whole group as it grows. The result is clamped to the
+	/// range the system allows, usually -20 to 19.
+	///
+	/// This calls `nice()` in the child before it executes the command. An unprivileged process
+	/// can only increase its niceness: a negative increment then makes spawning fail.
+	///
+	/// Zero, the default, leaves the niceness unchanged.
+	#[cfg(unix)]
+	pub fn nice(&mut self, increment: i32) -> &mut Self {
+		self.nice = increment;
+		self
+	}
+
+	/// Places the process in a cgroup, so that the whole group is confined to it.
+	///
+	/// `path` is the cgroup's directory in the cgroup v2 hierarchy, such as
+	/// `/sys/fs/cgroup/my-service/jobs`, which must already exist. The child moves itself into it
+	/// before it executes the command, by writing to its `cgroup.procs`. The processes it starts
+	/// then stay in the cgroup, so the kernel can enforce and measure the limits set on it for the
+	/// group as a whole. Unlike writing the leader's ID to `cgroup.procs` after spawning, no process
+	/// of the group ever runs outside of the cgroup.
+	///
+	/// This requires the unified (v2) cgroup hierarchy, available since Linux 4.5, and permission
+	/// to write to `cgroup.procs` both in the target cgroup and in the common ancestor of it and
+	/// our own cgroup; for an unprivileged process, this usually means a cgroup delegated to it by
+	/// the service manager. If the child can't move into the cgroup, for example because it
+	/// doesn't exist, spawning fails rather than running the group unconfined.
+	///
+	/// This is only available on Linux. By default, the process stays in our own cgroup.
+	#[cfg(target_os = "linux")]
+	pub fn cgroup(&mut self, path: impl AsRef<Path>) -> &mut Self {
+		self.cgroup = Some(path.as_ref().to_owned());
+		self
+	}
+
+	/// Sets whether stderr should be written to the same place as stdout, like `2>&1` in a shell.
+	///
+	/// When stdout is piped, both streams are then read from the child's stdout handle, in the
+	/// order they were written, as a terminal would show them; `wait_with_output()` returns all of
+	/// it in `stdout`, and an empty `stderr`. This avoids reading from two separate pipes, which
+	/// can't preserve the relative order of the output.
+	///
+	/// On Unix, this duplicates the child's stdout onto its stderr before the command executes, so
+	/// stderr follows whatever stdout was set to, and the stderr setting is ignored.
+	///
+	/// On Windows, both are instead given the same new pipe, so stdout is always piped. Once the
+	/// child is spawned, the command's stdout and stderr are reset to being inherited.
+	///
+	/// This is off by default.
+	pub fn merge_stderr_into_stdout(&mut self, merge: bool) -> &mut Self {
+		self.merge_stderr = merge;
+		self
+	}
+
 	/// Set the creation flags for the process.
+	///
+	/// The process is always created suspended, so it can be assigned to the job object before it
+	/// runs, and then resumed. If `CREATE_SUSPENDED` is set here, it's left suspended instead.
+	///
+	/// The flags for the builder's other options, like [`new_console`](Self::new_console), are
+	/// added to these. Flags set with [`CommandExt::creation_flags`] directly on the command are
+	/// replaced when spawning, as the standard library doesn't let us read them back: set them
+	/// here instead.
+	///
+	/// [`CommandExt::creation_flags`]: https://doc.rust-lang.org/std/os/windows/process/trait.CommandExt.html#tymethod.creation_flags
 	#[cfg(windows)]
 	pub fn creation_flags(&mut self, creation_flags: u32) -> &mut Self {
 		self.creation_flags = creation_flags;
 		self
 	}
+
+	/// Sets whether the process should be started in a new console process group.
+	///
+	/// This adds the `CREATE_NEW_PROCESS_GROUP` flag to the [creation flags](Self::creation_flags),
+	/// which is required to send console control events to the group, for example with
+	/// `send_control()` or [`terminate()`](crate::GroupChild::terminate). Otherwise the group can
+	/// only be stopped by killing it outright.
+	///
+	/// Processes in a new console process group ignore Ctrl-C by default, but not Ctrl-Break.
+	///
+	/// This is off by default.
+	#[cfg(windows)]
+	pub fn new_process_group(&mut self, new_process_group: bool) -> &mut Self {
+		self.new_process_group = new_process_group;
+		self
+	}
+
+	/// Sets whether the process should get a new console, instead of sharing ours.
+	///
+	/// This adds the `CREATE_NEW_CONSOLE` flag to the [creation flags](Self::creation_flags). A
+	/// console program then opens in its own window, and the processes it starts share that
+	/// console.
+	///
+	/// Console control events are only delivered to processes attached to the same console as the
+	/// one sending them, so [`send_control()`](crate::GroupChild::send_control) can't reach a group
+	/// with its own console, and [`terminate()`](crate::GroupChild::terminate) may not have any
+	/// effect on it: stop it with [`kill()`](crate::GroupChild::kill) instead.
+	///
+	/// This is off by default.
+	#[cfg(windows)]
+	pub fn new_console(&mut self, new_console: bool) -> &mut Self {
+		self.new_console = new_console;
+		self
+	}
+
+	/// Sets whether the process should run without a console window.
+	///
+	/// This adds the `CREATE_NO_WINDOW` flag to the [creation flags](Self::creation_flags). A
+	/// console program then gets a console without a window, which is what background servers
+	/// usually want, whether or not we have a console ourselves. This is ignored for programs which
+	/// aren't console programs, and if [`new_console`](Self::new_console) is also set.
+	///
+	/// As with `new_console`, the group doesn't share our console, so console control events from
+	/// [`send_control()`](crate::GroupChild::send_control) and
+	/// [`terminate()`](crate::GroupChild::terminate) can't reach it.
+	///
+	/// This is off by default.
+	#[cfg(windows)]
+	pub fn no_window(&mut self, no_window: bool) -> &mut Self {
+		self.no_window = no_window;
+		self
+	}
+
+	/// Sets whether the process should break away from the job object we're running in, if any.
+	///
+	/// When this process is itself in a job, for example under a CI agent, a service manager, or a
+	/// debugger, the child starts in that outer job too. Our own job is then nested inside it: the
+	/// group still works, but the outer job's limits also apply to the child, and if the outer job
+	/// is terminated or closed with kill-on-close set, it takes the group down with it.
+	///
+	/// This adds the `CREATE_BREAKAWAY_FROM_JOB` flag to the [creation flags](Self::creation_flags),
+	/// so the child leaves the outer job before it's assigned to ours. The outer job must allow
+	/// this with `JOB_OBJECT_LIMIT_BREAKAWAY_OK`, otherwise spawning fails with an access denied
+	/// error. If we're not in a job at all, this has no effect.
+	///
+	/// This only concerns the group leader leaving the outer job. The job object for the group
+	/// never allows breakaway itself, so processes started within the group stay in it.
+	///
+	/// This is off by default.
+	#[cfg(windows)]
+	pub fn allow_breakaway(&mut self, allow: bool) -> &mut Self {
+		self.allow_breakaway = allow;
+		self
+	}
+
+	/// Sets the priority class the process is created with.
+	///
+	/// This adds the corresponding flag, like `BELOW_NORMAL_PRIORITY_CLASS`, to the
+	/// [creation flags](Self::creation_flags). Processes started by the child inherit a class of
+	/// `Idle` or `BelowNormal`, so those apply to the whole group as it grows.
+	///
+	/// This is only available on Windows. By default, no class is set, so the process gets the
+	/// normal class, or inherits a lower one from us.
+	#[cfg(windows)]
+	pub fn priority_class(&mut self, class: PriorityClass) -> &mut Self {
+		self.priority_class = Some(class);
+		self
+	}
+
+	/// Limits how many processes can be running in the group at once.
+	///
+	/// Once the limit is reached, processes in the group fail to create more processes until some
+	/// exit. This must be at least 1, for the group leader itself.
+	///
+	/// This is only available on Windows, where it is enforced by the job object. By default,
+	/// there is no limit.
+	#[cfg(windows)]
+	pub fn max_processes(&mut self, max: u32) -> &mut Self {
+		self.job_limits.max_processes = Some(max);
+		self
+	}
+
+	/// Limits the memory committed by all processes in the group together, in bytes.
+	///
+	/// Once the limit is reached, memory allocations in the group fail.
+	///
+	/// This is only available on Windows, where it is enforced by the job object. By default,
+	/// there is no limit.
+	#[cfg(windows)]
+	pub fn memory_limit(&mut self, bytes: usize) -> &mut Self {
+		self.job_limits.memory = Some(bytes);
+		self
+	}
+
+	/// Limits the memory committed by each process in the group, in bytes.
+	///
+	/// Once the limit is reached, memory allocations in that process fail.
+	///
+	/// This is only available on Windows, where it is enforced by the job object. By default,
+	/// there is no limit.
+	#[cfg(windows)]
+	pub fn process_memory_limit(&mut self, bytes: usize) -> &mut Self {
+		self.job_limits.process_memory = Some(bytes);
+		self
+	}
+
+	/// Limits the user-mode CPU time used by all processes in the group together.
+	///
+	/// Once the limit is reached, every process in the group is terminated. Time spent in kernel
+	/// mode isn't counted.
+	///
+	/// This is only available on Windows, where it is enforced by the job object. By default,
+	/// there is no limit.
+	#[cfg(windows)]
+	pub fn cpu_time_limit(&mut self, limit: std::time::Duration) -> &mut Self {
+		self.job_limits.cpu_time = Some(limit);
+		self
+	}
+
+	/// Returns what the child needs to do before exec to match the builder's options, or `None`
+	/// if it doesn't need setting up at all.
+	///
+	/// `join_group` is the process group to join with `setpgid()`, for commands which can't do
+	/// that themselves.
+	#[cfg(unix)]
+	pub(crate) fn child_setup(
+		&self,
+		join_group: Option<i32>,
+	) -> std::io::Result<Option<ChildSetup>> {
+		#[cfg(target_os = "linux")]
+		let cgroup_procs = self
+			.cgroup
+			.as_ref()
+			.map(|path| CString::new(path.join("cgroup.procs").into_os_string().into_vec()))
+			.transpose()?;
+		#[cfg(target_os = "linux")]
+		let joins_cgroup = cgroup_procs.is_some();
+		#[cfg(not(target_os = "linux"))]
+		let joins_cgroup = false;
+
+		let needed = self.merge_stderr
+			|| self.new_session
+			|| self.foreground
+			|| !self.rlimits.is_empty()
+			|| self.nice != 0
+			|| joins_cgroup
+			|| join_group.is_some();
+		if !needed {
+			return Ok(None);
+		}
+
+		Ok(Some(ChildSetup {
+			merge_stderr: self.merge_stderr,
+			new_session: self.new_session,
+			join_group,
+			foreground: self.foreground,
+			nice: self.nice,
+			rlimits: self.rlimits.clone(),
+			#[cfg(target_os = "linux")]
+			cgroup_procs,
+		}))
+	}
+
+	/// Returns whether this builder still has to register the hook which runs the
+	/// [current setup](ChildSetup::run_current) on the command, counting it as registered.
+	#[cfg(unix)]
+	pub(crate) fn register_setup_hook(&mut self) -> bool {
+		!std::mem::replace(&mut self.setup_hook, true)
+	}
+
+	#[cfg(windows)]
+	pub(crate) fn windows_creation_flags(&self) -> u32 {
+		use winapi::um::winbase::{
+			CREATE_BREAKAWAY_FROM_JOB, CREATE_NEW_CONSOLE, CREATE_NEW_PROCESS_GROUP,
+			CREATE_NO_WINDOW,
+		};
+
+		let mut flags = self.creation_flags;
+		if self.new_process_group {
+			flags |= CREATE_NEW_PROCESS_GROUP;
+		}
+		if self.allow_breakaway {
+			flags |= CREATE_BREAKAWAY_FROM_JOB;
+		}
+		if self.new_console {
+			flags |= CREATE_NEW_CONSOLE;
+		}
+		if self.no_window {
+			flags |= CREATE_NO_WINDOW;
+		}
+		if let Some(class) = self.priority_class {
+			flags |= class.creation_flag();
+		}
+		flags
+	}
+}
+
+/// A Windows process priority class, for [`CommandGroupBuilder::priority_class()`].
+#[cfg(windows)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PriorityClass {
+	/// Only runs when the system is otherwise idle: `IDLE_PRIORITY_CLASS`.
+	Idle,
+
+	/// Runs below the normal priority: `BELOW_NORMAL_PRIORITY_CLASS`.
+	BelowNormal,
+
+	/// The default priority: `NORMAL_PRIORITY_CLASS`.
+	Normal,
+
+	/// Runs above the normal priority: `ABOVE_NORMAL_PRIORITY_CLASS`.
+	AboveNormal,
+
+	/// Runs before most other processes: `HIGH_PRIORITY_CLASS`.
+	High,
+}
+
+#[cfg(windows)]
+impl PriorityClass {
+	fn creation_flag(self) -> u32 {
+		use winapi::um::winbase::{
+			ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS,
+			IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+		};
+
+		match self {
+			Self::Idle => IDLE_PRIORITY_CLASS,
+			Self::BelowNormal => BELOW_NORMAL_PRIORITY_CLASS,
+			Self::Normal => NORMAL_PRIORITY_CLASS,
+			Self::AboveNormal => ABOVE_NORMAL_PRIORITY_CLASS,
+			Self::High => HIGH_PRIORITY_CLASS,
+		}
+	}
+}
+
+/// The command a builder spawns from, either borrowed from the caller or owned by the builder.
+pub(crate) enum BuilderCommand<'a, T> {
+	Borrowed(&'a mut T),
+	Owned(T),
+}
+
+impl<T> Deref for BuilderCommand<'_, T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		match self {
+			Self::Borrowed(command) => command,
+			Self::Owned(command) => command,
+		}
+	}
+}
+
+impl<T> DerefMut for BuilderCommand<'_, T> {
+	fn deref_mut(&mut self) -> &mut T {
+		match self {
+			Self::Borrowed(command) => command,
+			Self::Owned(command) => command,
+		}
+	}
+}
+
+/// Commands whose standard streams can be configured through a [`CommandGroupBuilder`].
+///
+/// This is implemented for std's [`Command`](std::process::Command), and for Tokio's when the
+/// `with-tokio` feature is enabled.
+pub trait CommandStdio {
+	/// Configures the command's stdin, like `Command::stdin()`.
+	fn set_stdin(&mut self, cfg: Stdio);
+
+	/// Configures the command's stdout, like `Command::stdout()`.
+	fn set_stdout(&mut self, cfg: Stdio);
+
+	/// Configures the command's stderr, like `Command::stderr()`.
+	fn set_stderr(&mut self, cfg: Stdio);
+}
+
+impl CommandStdio for std::process::Command {
+	fn set_stdin(&mut self, cfg: Stdio) {
+		self.stdin(cfg);
+	}
+
+	fn set_stdout(&mut self, cfg: Stdio) {
+		self.stdout(cfg);
+	}
+
+	fn set_stderr(&mut self, cfg: Stdio) {
+		self.stderr(cfg);
+	}
+}
+
+#[cfg(feature = "with-tokio")]
+impl CommandStdio for tokio::process::Command {
+	fn set_stdin(&mut self, cfg: Stdio) {
+		self.stdin(cfg);
+	}
+
+	fn set_stdout(&mut self, cfg: Stdio) {
+		self.stdout(cfg);
+	}
+
+	fn set_stderr(&mut self, cfg: Stdio) {
+		self.stderr(cfg);
+	}
+}
+
+impl<T: CommandStdio> CommandGroupBuilder<'_, T> {
+	/// Sets the configuration for the child's standard input (stdin) handle.
+	///
+	/// This is the same as calling `stdin()` on the command itself, but can be chained with the
+	/// group options, for example when the builder is passed around or stored.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::{Command, Stdio};
+	/// use command_group::CommandGroup;
+	///
+	/// let child = Command::new("cat")
+	///     .group()
+	///     .kill_on_drop(true)
+	///     .stdin(Stdio::piped())
+	///     .spawn()
+	///     .expect("cat command failed to start");
+	/// ```
+	pub fn stdin(&mut self, cfg: impl Into<Stdio>) -> &mut Self {
+		self.command.set_stdin(cfg.into());
+		self
+	}
+
+	/// Sets the configuration for the child's standard output (stdout) handle.
+	///
+	/// This is the same as calling `stdout()` on the command itself, but can be chained with the
+	/// group options.
+	pub fn stdout(&mut self, cfg: impl Into<Stdio>) -> &mut Self {
+		self.command.set_stdout(cfg.into());
+		self
+	}
+
+	/// Sets the configuration for the child's standard error (stderr) handle.
+	///
+	/// This is the same as calling `stderr()` on the command itself, but can be chained with the
+	/// group options. It has no effect if
+	/// [`merge_stderr_into_stdout`](CommandGroupBuilder::merge_stderr_into_stdout) is set.
+	pub fn stderr(&mut self, cfg: impl Into<Stdio>) -> &mut Self {
+		self.command.set_stderr(cfg.into());
+		self
+	}
+}
+
+/// The options which apply in the child, between fork and exec.
+///
+/// This is a snapshot of the builder's options, taken for each spawn and made current while the
+/// child is forked. The hook registered on the command only runs the current setup, so a command
+/// spawned again from another builder, or without one, doesn't keep the options of earlier spawns.
+#[cfg(unix)]
+#[derive(Debug)]
+pub(crate) struct ChildSetup {
+	merge_stderr: bool,
+	new_session: bool,
+	join_group: Option<i32>,
+	foreground: bool,
+	nice: i32,
+	rlimits: Vec<(Resource, u64, u64)>,
+	#[cfg(target_os = "linux")]
+	cgroup_procs: Option<CString>,
+}
+
+#[cfg(unix)]
+thread_local! {
+	/// The setup for the child being spawned by this thread, if any.
+	static CURRENT_SETUP: Cell<*const ChildSetup> = const { Cell::new(ptr::null()) };
+}
+
+/// Keeps a [`ChildSetup`] current until it's dropped, once the spawn is done.
+#[cfg(unix)]
+pub(crate) struct CurrentSetup<'a>(PhantomData<&'a ChildSetup>);
+
+#[cfg(unix)]
+impl Drop for CurrentSetup<'_> {
+	fn drop(&mut self) {
+		CURRENT_SETUP.with(|current| current.set(ptr::null()));
+	}
+}
+
+#[cfg(unix)]
+impl ChildSetup {
+	/// Makes this the setup which is run in children forked by this thread, for as long as the
+	/// returned guard is held.
+	pub(crate) fn make_current(&self) -> CurrentSetup<'_> {
+		CURRENT_SETUP.with(|current| current.set(self));
+		CurrentSetup(PhantomData)
+	}
+
+	/// Runs the current setup, if there is one, in the calling process.
+	///
+	/// This is the hook registered on commands, and runs in the child, between fork and exec. The
+	/// child has a copy of the spawning thread's memory, so the setup is still there. Hooks can't
+	/// be removed from a command, so one spawned from several builders has several: the first to
+	/// run takes the setup, and the others then do nothing, like they all do when there's none.
+	pub(crate) fn run_current() -> std::io::Result<()> {
+		let setup = CURRENT_SETUP.with(|current| current.replace(ptr::null()));
+		// SAFETY: the setup is kept alive by the parent's guard, and so by the child's copy of it
+		match unsafe { setup.as_ref() } {
+			Some(setup) => setup.run(),
+			None => Ok(()),
+		}
+	}
+
+	/// Sets up the child, in the calling process.
+	///
+	/// This runs in the child, between fork and exec.
+	fn run(&self) -> std::io::Result<()> {
+		use nix::unistd::{setpgid, Pid};
+
+		// move first, so that nothing the child does happens outside of the cgroup
+		#[cfg(target_os = "linux")]
+		if let Some(cgroup_procs) = self.cgroup_procs.as_deref() {
+			join_cgroup_child(cgroup_procs)?;
+		}
+
+		if self.merge_stderr {
+			merge_stderr_child()?;
+		}
+
+		if self.new_session {
+			// setsid() also creates a new process group, led by the child
+			setsid_child()?;
+		} else if let Some(pgid) = self.join_group {
+			setpgid(Pid::this(), Pid::from_raw(pgid))?;
+		}
+
+		if self.foreground {
+			// this runs after the child has joined its group
+			crate::tty::foreground_child()?;
+		}
+
+		if self.nice != 0 {
+			nice_child(self.nice)?;
+		}
+
+		for &(resource, soft, hard) in &self.rlimits {
+			setrlimit_child(resource, soft, hard)?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Points the calling process's stderr to its stdout.
+///
+/// This runs in the child, between fork and exec.
+#[cfg(unix)]
+fn merge_stderr_child() -> std::io::Result<()> {
+	use nix::{
+		libc::{STDERR_FILENO, STDOUT_FILENO},
+		unistd::dup2,
+	};
+
+	dup2(STDOUT_FILENO, STDERR_FILENO)?;
+	Ok(())
+}
+
+/// Sets a resource limit of the calling process.
+///
+/// This runs in the child, between fork and exec.
+#[cfg(unix)]
+fn setrlimit_child(resource: Resource, soft: u64, hard: u64) -> std::io::Result<()> {
+	use nix::{
+		libc::{rlim_t, RLIM_INFINITY},
+		sys::resource::setrlimit,
+	};
+
+	// values which don't fit are too large to be a limit anyway
+	let limit = |value: u64| rlim_t::try_from(value).unwrap_or(RLIM_INFINITY);
+	setrlimit(resource, limit(soft), limit(hard))?;
+	Ok(())
+}
+
+/// Moves the calling process into the cgroup with the given `cgroup.procs` file.
+///
+/// This runs in the child, between fork and exec.
+#[cfg(target_os = "linux")]
+fn join_cgroup_child(cgroup_procs: &CStr) -> std::io::Result<()> {
+	use nix::libc;
+	use std::io::Error;
+
+	let fd = unsafe { libc::open(cgroup_procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC) };
+	if fd < 0 {
+		return Err(Error::last_os_error());
+	}
+
+	// writing 0 moves the process which writes it
+	let written = unsafe { libc::write(fd, b"0".as_ptr().cast(), 1) };
+	let err = Error::last_os_error();
+	unsafe { libc::close(fd) };
+	if written < 0 {
+		return Err(err);
+	}
+	Ok(())
+}
+
+/// Changes the niceness of the calling process by `increment`.
+///
+/// This runs in the child, between fork and exec.
+#[cfg(unix)]
+fn nice_child(increment: i32) -> std::io::Result<()> {
+	use nix::{errno::Errno, libc};
+
+	// -1 is also a valid niceness, so errors can only be told apart with errno
+	Errno::clear();
+	if unsafe { libc::nice(increment) } == -1 {
+		match Errno::last() {
+			Errno::UnknownErrno => {}
+			errno => return Err(errno.into()),
+		}
+	}
+	Ok(())
+}
+
+/// Makes the calling process lead a new session, and so a new process group.
+///
+/// This runs in the child, between fork and exec.
+#[cfg(unix)]
+fn setsid_child() -> std::io::Result<()> {
+	use nix::{
+		errno::Errno,
+		unistd::{getpgrp, getpid, setsid},
+	};
+
+	match setsid() {
+		Ok(_) => Ok(()),
+		// setsid() fails if we already lead a process group; as we then still lead our own group,
+		// carry on without a new session
+		Err(Errno::EPERM) if getpgrp() == getpid() => Ok(()),
+		Err(err) => Err(err.into()),
+	}
 }
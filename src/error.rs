@@ -0,0 +1,154 @@
+use std::{fmt, io};
+
+/// A specialised [`Result`](std::result::Result) for operations on process groups.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An error from spawning, signalling, or waiting on a process group.
+///
+/// This tells apart the step that failed, so that supervisors can handle for example a group that
+/// has already exited differently from a failure to deliver a signal, without inspecting
+/// messages. The underlying [`io::Error`] is available as the [`source()`], and through
+/// [`kind()`](Self::kind) and [`raw_os_error()`](Self::raw_os_error).
+///
+/// This converts into an [`io::Error`], giving back the original error where there is one, so
+/// `?` keeps working in functions which return [`io::Result`].
+///
+/// [`source()`]: std::error::Error::source
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+	/// The group, or the process targeted in it, has already exited.
+	///
+	/// This converts to an [`InvalidInput`](io::ErrorKind::InvalidInput) I/O error, like std's
+	/// `Child::kill()` returns once the child has been waited on.
+	AlreadyExited,
+
+	/// The command could not be spawned.
+	Spawn(io::Error),
+
+	/// A signal or other request to exit could not be delivered to the group.
+	Signal(io::Error),
+
+	/// Waiting on the group failed.
+	Wait(io::Error),
+
+	/// Any other I/O error, such as when reading the group's output or querying its processes.
+	Io(io::Error),
+}
+
+impl Error {
+	/// Returns the kind of the underlying I/O error.
+	///
+	/// This is [`InvalidInput`](io::ErrorKind::InvalidInput) for [`AlreadyExited`](Self::AlreadyExited).
+	pub fn kind(&self) -> io::ErrorKind {
+		match self.io() {
+			Some(err) => err.kind(),
+			None => io::ErrorKind::InvalidInput,
+		}
+	}
+
+	/// Returns the OS error code of the underlying I/O error, if it has one.
+	pub fn raw_os_error(&self) -> Option<i32> {
+		self.io().and_then(io::Error::raw_os_error)
+	}
+
+	fn io(&self) -> Option<&io::Error> {
+		match self {
+			Self::AlreadyExited => None,
+			Self::Spawn(err) | Self::Signal(err) | Self::Wait(err) | Self::Io(err) => Some(err),
+		}
+	}
+
+	pub(crate) fn spawn(err: io::Error) -> Self {
+		Self::classify(err, Self::Spawn)
+	}
+
+	pub(crate) fn signal(err: io::Error) -> Self {
+		Self::classify(err, Self::Signal)
+	}
+
+	pub(crate) fn wait(err: io::Error) -> Self {
+		Self::classify(err, Self::Wait)
+	}
+
+	/// Wraps an I/O error in the given variant, unless it already carries an [`Error`].
+	///
+	/// Internally, errors are passed around as I/O errors, and converted at the public API. Those
+	/// which were already classified, like [`AlreadyExited`](Self::AlreadyExited), are unwrapped
+	/// here rather than being wrapped again.
+	fn classify(err: io::Error, variant: fn(io::Error) -> Self) -> Self {
+		if err.get_ref().is_some_and(|inner| inner.is::<Self>()) {
+			let inner = err.into_inner().expect("checked above");
+			*inner.downcast::<Self>().expect("checked above")
+		} else {
+			variant(err)
+		}
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::AlreadyExited => f.write_str("the process group has already exited"),
+			Self::Spawn(err) => write!(f, "failed to spawn the process group: {err}"),
+			Self::Signal(err) => write!(f, "failed to signal the process group: {err}"),
+			Self::Wait(err) => write!(f, "failed to wait on the process group: {err}"),
+			Self::Io(err) => err.fmt(f),
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::AlreadyExited => None,
+			Self::Spawn(err) | Self::Signal(err) | Self::Wait(err) => Some(err),
+			// displayed as itself, so it's not also a source
+			Self::Io(err) => err.source(),
+		}
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(err: io::Error) -> Self {
+		Self::classify(err, Self::Io)
+	}
+}
+
+impl From<Error> for io::Error {
+	fn from(err: Error) -> Self {
+		match err {
+			Error::AlreadyExited => io::Error::new(io::ErrorKind::InvalidInput, err),
+			Error::Spawn(err) | Error::Signal(err) | Error::Wait(err) | Error::Io(err) => err,
+		}
+	}
+}
+
+/// Treats the group having already exited as success.
+///
+/// This is for when the group is being stopped anyway, and may exit on its own at any moment.
+pub(crate) fn ignore_exited(res: Result<()>) -> Result<()> {
+	match res {
+		Err(Error::AlreadyExited) => Ok(()),
+		res => res,
+	}
+}
+
+/// Treats the child having closed its end of a pipe as success.
+///
+/// This is for writing input which the child is free to not read in full.
+pub(crate) fn ignore_broken_pipe(res: io::Result<()>) -> io::Result<()> {
+	match res {
+		Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+		res => res,
+	}
+}
+
+/// Converts the error from sending a signal, recognising when its target no longer exists.
+#[cfg(unix)]
+pub(crate) fn kill_error(errno: nix::errno::Errno) -> io::Error {
+	match errno {
+		nix::errno::Errno::ESRCH => Error::AlreadyExited.into(),
+		errno => errno.into(),
+	}
+}
@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+/// Accounting information for a job object, covering every process that was ever in the group.
+///
+/// This is obtained with [`GroupChild::job_accounting()`](crate::GroupChild::job_accounting) (or
+/// the async equivalent), and is the Windows counterpart to the `Rusage` available on Unix. Unlike
+/// it, this can be queried while the group is still running.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct JobAccounting {
+	/// Total CPU time spent in user mode by all processes in the job.
+	pub user_time: Duration,
+
+	/// Total CPU time spent in kernel mode by all processes in the job.
+	pub kernel_time: Duration,
+
+	/// Peak memory committed by all processes in the job at once, in bytes.
+	pub peak_memory: usize,
+
+	/// Total number of processes that were ever in the job, including those that have exited.
+	pub total_processes: u32,
+}
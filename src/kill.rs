@@ -0,0 +1,94 @@
+use crate::{Error, Result};
+
+#[cfg(unix)]
+use nix::{
+	sys::signal::{killpg, Signal},
+	unistd::Pid,
+};
+
+#[cfg(windows)]
+use std::{
+	os::windows::io::{AsRawHandle, OwnedHandle},
+	sync::Arc,
+};
+#[cfg(windows)]
+use winapi::um::winnt::HANDLE;
+
+/// A handle which can kill a process group without owning its child.
+///
+/// This is obtained with [`GroupChild::kill_handle()`](crate::GroupChild::kill_handle) (or the
+/// async equivalent), and can be cloned and sent to other threads or tasks, for example to
+/// implement a watchdog or cancellation.
+///
+/// On Unix, this holds the process group ID. Once the group has exited _and_ been waited on, the
+/// kernel may reuse that ID for an unrelated group, which this handle would then signal. Only use
+/// it while the group is known to be alive, or while its owner hasn't yet waited on it.
+///
+/// On Windows, this holds a duplicate of the job object handle, so it stays valid even after the
+/// original child is dropped. As a consequence, `kill_on_drop` will not take effect until all
+/// kill handles for the group have also been dropped.
+#[derive(Clone, Debug)]
+pub struct GroupKill {
+	#[cfg(unix)]
+	pgid: Pid,
+	#[cfg(windows)]
+	job: Arc<OwnedHandle>,
+}
+
+impl GroupKill {
+	#[cfg(unix)]
+	pub(crate) fn new(pgid: Pid) -> Self {
+		Self { pgid }
+	}
+
+	#[cfg(windows)]
+	pub(crate) fn new(job: &OwnedHandle) -> std::io::Result<Self> {
+		Ok(Self {
+			job: Arc::new(job.try_clone()?),
+		})
+	}
+
+	/// Forces the process group to exit.
+	///
+	/// This is equivalent to sending a SIGKILL on Unix platforms, and to terminating the job
+	/// object on Windows.
+	///
+	/// This does not wait for the group to exit, nor does it reap it: the owner of the child
+	/// should still wait on it. If the group no longer exists, [`Error::AlreadyExited`] is
+	/// returned.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use std::{process::Command, thread, time::Duration};
+	/// use command_group::CommandGroup;
+	///
+	/// let mut child = Command::new("yes").group_spawn().expect("yes command didn't start");
+	/// let killer = child.kill_handle().expect("failed to get a kill handle");
+	/// thread::spawn(move || {
+	///     thread::sleep(Duration::from_secs(1));
+	///     killer.kill().expect("command wasn't running");
+	/// });
+	/// child.wait().expect("command wasn't running");
+	/// ```
+	pub fn kill(&self) -> Result<()> {
+		#[cfg(unix)]
+		{
+			use crate::UnixChildExt;
+			self.signal(Signal::SIGKILL)
+		}
+
+		#[cfg(windows)]
+		{
+			crate::winres::terminate_job(self.job.as_raw_handle() as HANDLE, 1)
+				.map_err(Error::signal)
+		}
+	}
+}
+
+#[cfg(unix)]
+impl crate::UnixChildExt for GroupKill {
+	fn signal(&self, sig: Signal) -> Result<()> {
+		killpg(self.pgid, sig).map_err(|err| Error::signal(crate::error::kill_error(err)))
+	}
+}
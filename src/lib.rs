@@ -3,6 +3,10 @@
 	feature = "with-tokio",
 	doc = "With Tokio, the [`AsyncCommandGroup`] trait extends [`tokio::process::Command`](::tokio::process::Command)."
 )]
+#![cfg_attr(
+	feature = "with-async-io",
+	doc = "With other executors, the [`AsyncIoCommandGroup`] trait spawns groups from std’s `Command` which can be waited on asynchronously, using [async-io](https://docs.rs/async-io)."
+)]
 #![doc = "\n"]
 #![cfg_attr(
 	unix,
@@ -12,6 +16,7 @@
 	all(unix, feature = "with-tokio"),
 	doc = "[`AsyncGroupChild`], Tokio’s [`Child`](::tokio::process::Child)"
 )]
+#![cfg_attr(all(unix, feature = "with-async-io"), doc = "[`AsyncIoGroupChild`],")]
 #![cfg_attr(unix, doc = "and std’s [`Child`](std::process::Child)).")]
 #![doc(html_favicon_url = "https://watchexec.github.io/logo:command-group.svg")]
 #![doc(html_logo_url = "https://watchexec.github.io/logo:command-group.svg")]
@@ -25,20 +30,74 @@ mod unix_ext;
 #[cfg(feature = "with-tokio")]
 pub mod tokio;
 
+#[cfg(feature = "with-async-io")]
+pub mod async_io;
+
 pub mod builder;
 
+mod error;
+
+mod kill;
+
+mod output;
+
+#[cfg(windows)]
+mod signal;
+
+#[cfg(unix)]
+mod rusage;
+
+#[cfg(unix)]
+mod status;
+
+#[cfg(target_os = "linux")]
+mod pidfd;
+
+#[cfg(unix)]
+mod procs;
+
+#[cfg(unix)]
+mod tty;
+
+#[cfg(windows)]
+mod job;
+
 #[cfg(windows)]
 pub(crate) mod winres;
 
+#[cfg(windows)]
+#[doc(inline)]
+pub use crate::signal::{ControlEvent, Signal};
 #[cfg(unix)]
 #[doc(inline)]
 pub use crate::unix_ext::UnixChildExt;
 #[cfg(unix)]
 #[doc(no_inline)]
+pub use nix::sys::resource::Resource;
+#[cfg(unix)]
+#[doc(no_inline)]
 pub use nix::sys::signal::Signal;
 
 #[doc(inline)]
-pub use crate::stdlib::child::GroupChild;
+pub use crate::error::{Error, Result};
+#[cfg(windows)]
+#[doc(inline)]
+pub use crate::job::JobAccounting;
+#[doc(inline)]
+pub use crate::kill::GroupKill;
+#[cfg(feature = "with-tokio")]
+#[doc(inline)]
+pub use crate::output::TimedOutput;
+#[doc(inline)]
+pub use crate::output::{LimitedOutput, OutputLine, OutputSource};
+#[cfg(unix)]
+#[doc(inline)]
+pub use crate::rusage::Rusage;
+#[cfg(unix)]
+#[doc(inline)]
+pub use crate::status::GroupStatus;
+#[doc(inline)]
+pub use crate::stdlib::child::{ChildStdio, GroupChild};
 pub use crate::stdlib::CommandGroup;
 
 #[cfg(feature = "with-tokio")]
@@ -46,3 +105,9 @@ pub use crate::stdlib::CommandGroup;
 pub use crate::tokio::child::AsyncGroupChild;
 #[cfg(feature = "with-tokio")]
 pub use crate::tokio::AsyncCommandGroup;
+
+#[cfg(feature = "with-async-io")]
+#[doc(inline)]
+pub use crate::async_io::child::AsyncIoGroupChild;
+#[cfg(feature = "with-async-io")]
+pub use crate::async_io::AsyncIoCommandGroup;
@@ -0,0 +1,92 @@
+use std::{mem, process::Output};
+
+/// Which of the group leader's output streams some output was read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OutputSource {
+	/// The standard output stream.
+	Stdout,
+
+	/// The standard error stream.
+	Stderr,
+}
+
+/// The output of a group whose capture was limited in size, from `wait_with_output_limited()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LimitedOutput {
+	/// The status and captured output, up to the limit for each stream.
+	pub output: Output,
+
+	/// Whether STDOUT went over its limit, so that some of it was discarded.
+	pub stdout_truncated: bool,
+
+	/// Whether STDERR went over its limit, so that some of it was discarded.
+	pub stderr_truncated: bool,
+}
+
+/// The output of a group which was given a time limit, from `wait_with_output_timeout()`.
+#[cfg(feature = "with-tokio")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimedOutput {
+	/// The status and all the output captured, including before the group was killed.
+	pub output: Output,
+
+	/// Whether the time ran out, so that the group was killed.
+	pub timed_out: bool,
+}
+
+/// A line of output from the group leader, from `stream_output()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputLine {
+	/// Which stream the line was read from.
+	pub source: OutputSource,
+
+	/// The line, without its `\n` or `\r\n` ending.
+	///
+	/// The last line of a stream may not have had an ending, if the stream was closed right after.
+	pub line: Vec<u8>,
+}
+
+/// Splits the output read from a stream into lines, holding on to a partial line until the rest
+/// of it is read.
+#[derive(Debug)]
+pub(crate) struct LineSplitter {
+	source: OutputSource,
+	partial: Vec<u8>,
+}
+
+impl LineSplitter {
+	pub(crate) fn new(source: OutputSource) -> Self {
+		Self {
+			source,
+			partial: Vec::new(),
+		}
+	}
+
+	/// Adds what was read, passing any lines it completes to `f`.
+	pub(crate) fn push(&mut self, mut data: &[u8], f: &mut impl FnMut(OutputLine)) {
+		while let Some(end) = data.iter().position(|&b| b == b'\n') {
+			self.partial.extend_from_slice(&data[..end]);
+			data = &data[end + 1..];
+			self.emit(f);
+		}
+		self.partial.extend_from_slice(data);
+	}
+
+	/// Passes the last line to `f`, once the stream has been closed without ending it.
+	pub(crate) fn finish(mut self, f: &mut impl FnMut(OutputLine)) {
+		if !self.partial.is_empty() {
+			self.emit(f);
+		}
+	}
+
+	fn emit(&mut self, f: &mut impl FnMut(OutputLine)) {
+		let mut line = mem::take(&mut self.partial);
+		if line.last() == Some(&b'\r') {
+			line.pop();
+		}
+		f(OutputLine {
+			source: self.source,
+			line,
+		});
+	}
+}
@@ -0,0 +1,36 @@
+use std::{
+	os::fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+	ptr,
+};
+
+use nix::{errno::Errno, libc, sys::signal::Signal};
+
+/// Opens a pidfd for the given process, if supported by the kernel (Linux 5.3+).
+///
+/// The pidfd becomes readable once the process exits, which lets us wait on it like any other fd.
+pub(crate) fn pidfd_open(pid: u32) -> Option<OwnedFd> {
+	let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+	if fd < 0 {
+		None
+	} else {
+		// SAFETY: the syscall succeeded, so this is a new fd that nothing else owns
+		Some(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+	}
+}
+
+/// Sends a signal to the process a pidfd refers to.
+///
+/// Unlike `kill()`, this can't reach another process which was given the same PID: once the
+/// process has been reaped, this fails with `ESRCH`.
+pub(crate) fn pidfd_send_signal(fd: BorrowedFd<'_>, sig: Signal) -> nix::Result<()> {
+	let res = unsafe {
+		libc::syscall(
+			libc::SYS_pidfd_send_signal,
+			fd.as_raw_fd(),
+			sig as libc::c_int,
+			ptr::null::<libc::siginfo_t>(),
+			0,
+		)
+	};
+	Errno::result(res).map(drop)
+}
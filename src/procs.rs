@@ -0,0 +1,101 @@
+use std::io::Result;
+
+use nix::unistd::Pid;
+
+/// Lists the processes currently in a process group.
+///
+/// On Linux, this reads `/proc`, and leaves out zombies. On macOS, this asks the kernel with
+/// `proc_listpgrppids`, which includes zombies. Other platforms have no way to do this without
+/// scanning the whole process table, so this isn't supported there.
+pub(crate) fn group_pids(pgid: Pid) -> Result<Vec<Pid>> {
+	imp::group_pids(pgid)
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+	use std::{fs, io::Result};
+
+	use nix::unistd::Pid;
+
+	pub fn group_pids(pgid: Pid) -> Result<Vec<Pid>> {
+		let mut pids = Vec::new();
+		for entry in fs::read_dir("/proc")? {
+			let entry = entry?;
+			let Some(pid) = entry
+				.file_name()
+				.to_str()
+				.and_then(|name| name.parse().ok())
+			else {
+				continue;
+			};
+
+			// the process may exit while we're looking at it
+			let Ok(stat) = fs::read_to_string(entry.path().join("stat")) else {
+				continue;
+			};
+
+			// the command name is in parentheses and may contain anything, so skip past it; it's
+			// followed by the state, the parent's pid, and the process group id
+			let Some((_, fields)) = stat.rsplit_once(')') else {
+				continue;
+			};
+			let mut fields = fields.split_whitespace();
+			let state = fields.next();
+			let pgrp = fields.nth(1).and_then(|pgrp| pgrp.parse().ok());
+			if state != Some("Z") && pgrp == Some(pgid.as_raw()) {
+				pids.push(Pid::from_raw(pid));
+			}
+		}
+
+		Ok(pids)
+	}
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+	use std::{
+		io::{Error, Result},
+		mem, ptr,
+	};
+
+	use nix::{libc, unistd::Pid};
+
+	pub fn group_pids(pgid: Pid) -> Result<Vec<Pid>> {
+		const PID_SIZE: usize = mem::size_of::<libc::pid_t>();
+
+		// ask for the size needed first, then leave some room for processes started meanwhile
+		let needed = unsafe { libc::proc_listpgrppids(pgid.as_raw(), ptr::null_mut(), 0) };
+		if needed < 0 {
+			return Err(Error::last_os_error());
+		}
+
+		let mut pids: Vec<libc::pid_t> = vec![0; needed as usize / PID_SIZE + 16];
+		let written = unsafe {
+			libc::proc_listpgrppids(
+				pgid.as_raw(),
+				pids.as_mut_ptr().cast(),
+				(pids.len() * PID_SIZE) as libc::c_int,
+			)
+		};
+		if written < 0 {
+			return Err(Error::last_os_error());
+		}
+
+		pids.truncate(written as usize / PID_SIZE);
+		Ok(pids.into_iter().map(Pid::from_raw).collect())
+	}
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod imp {
+	use std::io::{Error, ErrorKind, Result};
+
+	use nix::unistd::Pid;
+
+	pub fn group_pids(_pgid: Pid) -> Result<Vec<Pid>> {
+		Err(Error::new(
+			ErrorKind::Unsupported,
+			"listing the processes in a group isn't supported on this platform",
+		))
+	}
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use nix::libc;
+
+/// Resource usage of a process group, as reported by the kernel when its processes are reaped.
+///
+/// This is obtained with [`GroupChild::wait_with_rusage()`](crate::GroupChild::wait_with_rusage),
+/// and covers every process reaped through the group child. As with
+/// [`wait_all()`](crate::GroupChild::wait_all), that's the leader plus any other direct children
+/// of this process in the group. The usage of processes the leader itself waited on is included
+/// in the leader's.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Rusage {
+	/// Total CPU time spent in user mode, summed across all reaped processes.
+	pub user_time: Duration,
+
+	/// Total CPU time spent in kernel mode, summed across all reaped processes.
+	pub system_time: Duration,
+
+	/// Peak resident set size of the largest reaped process, in bytes.
+	///
+	/// This is the maximum of each process's own peak, not the peak of the group as a whole,
+	/// which the kernel doesn't track.
+	pub max_rss: u64,
+}
+
+impl Rusage {
+	pub(crate) fn add(&mut self, usage: &libc::rusage) {
+		self.user_time += timeval_to_duration(usage.ru_utime);
+		self.system_time += timeval_to_duration(usage.ru_stime);
+
+		// Linux and most BSDs report this in kilobytes, but Apple platforms use bytes
+		let max_rss = u64::try_from(usage.ru_maxrss).unwrap_or(0);
+		#[cfg(not(target_vendor = "apple"))]
+		let max_rss = max_rss.saturating_mul(1024);
+		self.max_rss = self.max_rss.max(max_rss);
+	}
+}
+
+fn timeval_to_duration(tv: libc::timeval) -> Duration {
+	Duration::new(
+		u64::try_from(tv.tv_sec).unwrap_or(0),
+		u32::try_from(tv.tv_usec).unwrap_or(0) * 1000,
+	)
+}
@@ -0,0 +1,45 @@
+/// A request to exit which can be sent to a process group on Windows.
+///
+/// Windows has no signals: this maps the common Unix cases onto what the platform offers, so that
+/// [`GroupChild::kill_with()`](crate::GroupChild::kill_with) can be called the same way on both
+/// platforms. On Unix, `Signal` is instead nix’s signal enum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Signal {
+	/// Forces the group to exit, by terminating its job object. Akin to SIGKILL.
+	Kill,
+
+	/// Asks the group to exit, falling back to killing it. Akin to SIGTERM.
+	///
+	/// This is the same as [`GroupChild::terminate()`](crate::GroupChild::terminate).
+	Terminate,
+
+	/// Sends a `CTRL_BREAK_EVENT` to the group. Akin to SIGINT.
+	///
+	/// This requires the group to have been created with the `CREATE_NEW_PROCESS_GROUP` creation
+	/// flag, and fails with [`Unsupported`](std::io::ErrorKind::Unsupported) otherwise.
+	Interrupt,
+}
+
+/// A console control event which can be sent to a process group on Windows.
+///
+/// This is sent with [`GroupChild::send_control()`](crate::GroupChild::send_control), and lets
+/// console programs shut down gracefully, as if the user had pressed the keys in their console.
+///
+/// Control events can only be sent to groups created with
+/// [`new_process_group`](crate::builder::CommandGroupBuilder::new_process_group).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ControlEvent {
+	/// Sends a `CTRL_C_EVENT`.
+	///
+	/// Windows can't limit this event to a process group, and processes in a new process group
+	/// ignore it unless they re-enable it with `SetConsoleCtrlHandler`. Prefer [`CtrlBreak`]
+	/// unless the program is known to handle Ctrl-C this way.
+	///
+	/// [`CtrlBreak`]: ControlEvent::CtrlBreak
+	CtrlC,
+
+	/// Sends a `CTRL_BREAK_EVENT`, which most console programs treat as a request to exit.
+	CtrlBreak,
+}
@@ -0,0 +1,44 @@
+use std::{
+	io::{Error, Result},
+	os::unix::process::ExitStatusExt,
+	process::ExitStatus,
+};
+
+use nix::sys::signal::Signal;
+
+/// A change in the state of a process group, as seen through its leader.
+///
+/// This is obtained with [`GroupChild::wait_status()`](crate::GroupChild::wait_status), which
+/// unlike `wait()` also reports when the group is stopped and continued, for example by job control
+/// signals like `SIGTSTP` and `SIGCONT`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GroupStatus {
+	/// The leader exited normally, with this exit code.
+	Exited(i32),
+
+	/// The leader was terminated by this signal.
+	Signaled(Signal),
+
+	/// The leader was stopped by this signal, and can be resumed with `SIGCONT`.
+	Stopped(Signal),
+
+	/// The leader was resumed after being stopped.
+	Continued,
+}
+
+impl GroupStatus {
+	/// Converts the exit status of a leader which has terminated.
+	///
+	/// This fails if the leader was terminated by a signal that [`Signal`] doesn't know of, such
+	/// as a realtime signal.
+	pub(crate) fn from_exit(status: ExitStatus) -> Result<Self> {
+		match (status.code(), status.signal()) {
+			(Some(code), _) => Ok(Self::Exited(code)),
+			(None, Some(sig)) => Signal::try_from(sig)
+				.map(Self::Signaled)
+				.map_err(Error::from),
+			(None, None) => unreachable!("a terminated process either exited or was signaled"),
+		}
+	}
+}
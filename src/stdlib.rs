@@ -1,12 +1,9 @@
 //! Implementation of process group extensions for the
 //! standard library’s [`Command` type](std::process::Command).
 
-use std::{
-	io::Result,
-	process::{Command, ExitStatus, Output},
-};
+use std::process::{Command, ExitStatus, Output, Stdio};
 
-use crate::{builder::CommandGroupBuilder, GroupChild};
+use crate::{builder::CommandGroupBuilder, ChildStdio, Error, GroupChild, Result};
 
 #[doc(inline)]
 pub use erased::ErasedChild;
@@ -44,9 +41,60 @@ pub trait CommandGroup {
 		self.group().spawn()
 	}
 
-	/// Converts the implementor into a [`CommandGroupBuilder`](crate::CommandGroupBuilder), which can be used to
-	/// set flags that are not available on the `Command` type.
-	fn group(&mut self) -> CommandGroupBuilder<std::process::Command>;
+	/// Executes the command as a child process group, returning a handle to it and, separately,
+	/// its stdio handles.
+	///
+	/// This is the same as [`group_spawn()`](Self::group_spawn) followed by
+	/// [`GroupChild::take_stdio()`], for when the stdio handles are owned by different code than
+	/// the group.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use std::io::{Read, Write};
+	/// use std::process::{Command, Stdio};
+	/// use command_group::CommandGroup;
+	///
+	/// let (mut child, stdio) = Command::new("cat")
+	///         .stdin(Stdio::piped())
+	///         .stdout(Stdio::piped())
+	///         .group_spawn_split()
+	///         .expect("cat command failed to start");
+	///
+	/// let mut stdin = stdio.stdin.expect("stdin is piped");
+	/// stdin.write_all(b"hello").expect("failed to write to child");
+	/// drop(stdin);
+	///
+	/// let mut output = String::new();
+	/// stdio.stdout.expect("stdout is piped").read_to_string(&mut output).expect("failed to read from child");
+	/// child.wait().expect("command wasn't running");
+	/// ```
+	fn group_spawn_split(&mut self) -> Result<(GroupChild, ChildStdio)> {
+		let mut child = self.group_spawn()?;
+		let stdio = child.take_stdio();
+		Ok((child, stdio))
+	}
+
+	/// Converts the implementor into a [`CommandGroupBuilder`], which can be used to set flags
+	/// that are not available on the `Command` type.
+	fn group(&mut self) -> CommandGroupBuilder<'_, std::process::Command>;
+
+	/// Converts the implementor into a [`CommandGroupBuilder`] which owns it, so that it can be
+	/// stored and spawned from later.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let mut builder = Command::new("ls").into_group();
+	/// builder.kill_on_drop(true);
+	/// builder.spawn().expect("ls command failed to start");
+	/// ```
+	fn into_group(self) -> CommandGroupBuilder<'static, std::process::Command>
+	where
+		Self: Sized;
 
 	/// Executes the command as a child process group, waiting for it to finish and
 	/// collecting all of its output.
@@ -81,6 +129,44 @@ pub trait CommandGroup {
 			.and_then(|child| child.wait_with_output())
 	}
 
+	/// Executes the command as a child process group, waiting for it to finish and collecting its
+	/// output as text.
+	///
+	/// This pipes stdout and stderr, replacing whatever they were set to, runs the group like
+	/// [`group_output()`](Self::group_output), and decodes both streams as UTF-8. Invalid UTF-8 is
+	/// replaced with `U+FFFD REPLACEMENT CHARACTER`, as with [`String::from_utf8_lossy`].
+	///
+	/// On Windows, this creates a job object instead of a POSIX process group.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let (status, stdout, stderr) = Command::new("git")
+	///                      .arg("status")
+	///                      .group_output_string()
+	///                      .expect("failed to execute process");
+	///
+	/// print!("{stdout}");
+	/// eprint!("{stderr}");
+	/// assert!(status.success());
+	/// ```
+	fn group_output_string(&mut self) -> Result<(ExitStatus, String, String)> {
+		let output = self
+			.group()
+			.stdout(Stdio::piped())
+			.stderr(Stdio::piped())
+			.spawn()?
+			.wait_with_output()?;
+		Ok((
+			output.status,
+			String::from_utf8_lossy(&output.stdout).into_owned(),
+			String::from_utf8_lossy(&output.stderr).into_owned(),
+		))
+	}
+
 	/// Executes a command as a child process group, waiting for it to finish and
 	/// collecting its status.
 	///
@@ -106,10 +192,47 @@ pub trait CommandGroup {
 	fn group_status(&mut self) -> Result<ExitStatus> {
 		self.group_spawn().and_then(|mut child| child.wait())
 	}
+
+	/// Executes the command either as a child process group or as a plain child, returning an
+	/// [`ErasedChild`] handle to it.
+	///
+	/// This lets the choice of grouping the command be made at runtime, without having to
+	/// branch and wrap the child by hand.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let grouped = std::env::var_os("NO_GROUP").is_none();
+	/// let mut child = Command::new("ls")
+	///         .spawn_erased(grouped)
+	///         .expect("ls command failed to start");
+	/// child.wait().expect("ls command wasn't running");
+	/// ```
+	fn spawn_erased(&mut self, grouped: bool) -> Result<ErasedChild> {
+		let mut builder = self.group();
+		if grouped {
+			builder.spawn().map(ErasedChild::from)
+		} else {
+			builder
+				.command
+				.spawn()
+				.map(ErasedChild::from)
+				.map_err(Error::spawn)
+		}
+	}
 }
 
 impl CommandGroup for Command {
 	fn group(&mut self) -> CommandGroupBuilder<'_, Command> {
 		CommandGroupBuilder::new(self)
 	}
+
+	fn into_group(self) -> CommandGroupBuilder<'static, Command> {
+		CommandGroupBuilder::owned(self)
+	}
 }
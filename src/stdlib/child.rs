@@ -1,7 +1,9 @@
 use std::{
 	fmt,
-	io::{Read, Result},
-	process::{Child, ExitStatus, Output},
+	io::{self, Read, Write},
+	process::{Child, ChildStderr, ChildStdin, ChildStdout, ExitStatus, Output},
+	thread,
+	time::Duration,
 };
 
 #[cfg(unix)]
@@ -9,14 +11,20 @@ use unix::ChildImp;
 #[cfg(windows)]
 use windows::ChildImp;
 
+use crate::{
+	error::{ignore_broken_pipe, ignore_exited},
+	output::LineSplitter,
+	Error, GroupKill, LimitedOutput, OutputLine, OutputSource, Result, Signal,
+};
 #[cfg(unix)]
-use crate::UnixChildExt;
-
-#[cfg(unix)]
-use nix::sys::signal::Signal;
+use crate::{GroupStatus, UnixChildExt};
 
 #[cfg(windows)]
-use winapi::um::winnt::HANDLE;
+use crate::winres::JobPort;
+#[cfg(target_os = "linux")]
+use std::os::fd::BorrowedFd;
+#[cfg(windows)]
+use std::os::windows::io::BorrowedHandle;
 
 #[cfg(unix)]
 mod unix;
@@ -28,6 +36,23 @@ mod windows;
 /// This wraps the [`Child`] type in the standard library with methods that work
 /// with process groups.
 ///
+/// # Process ID reuse
+///
+/// On Unix, the leader's process ID, and with it the process group ID, stays reserved until the
+/// leader has been reaped. The group ID stays reserved after that for as long as any process
+/// remains in the group, such as one the leader left running in the background.
+///
+/// This keeps track of the leader's exit itself, so that it is never confused with another process
+/// which was given the same ID. On Linux 5.3 and later, [`signal_leader()`](Self::signal_leader)
+/// also goes through a pidfd opened at spawn, so it can't reach another process even if the leader
+/// was reaped through [`inner()`](Self::inner). Elsewhere, reaping the leader that way leaves a
+/// window in which its ID may be reused before this notices that it has exited.
+///
+/// Signals to the whole group are sent by group ID, on every platform. Once the leader has been
+/// reaped and the last process has left the group, the kernel may reuse its ID for an unrelated
+/// group, which these would then reach. Long-lived supervisors should stop signalling a group once
+/// they have waited on it.
+///
 /// # Examples
 ///
 /// ```should_panic
@@ -51,27 +76,96 @@ pub struct GroupChild {
 
 impl fmt::Debug for GroupChild {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		f.debug_struct("GroupChild").finish()
+		// only what's already known, as this shouldn't block or reap anything
+		let inner = self.imp.inner_ref();
+		f.debug_struct("GroupChild")
+			.field("id", &self.id())
+			.field("group_id", &self.group_id())
+			.field("exit_status", &self.exitstatus)
+			.field("stdin", &inner.stdin.is_some())
+			.field("stdout", &inner.stdout.is_some())
+			.field("stderr", &inner.stderr.is_some())
+			.finish()
 	}
 }
 
+/// The stdio handles of a group leader, taken out of its [`GroupChild`].
+///
+/// This is returned by [`GroupChild::take_stdio()`] and
+/// [`CommandGroup::group_spawn_split()`](crate::CommandGroup::group_spawn_split). Each handle is
+/// only there if the corresponding stream was piped.
+#[derive(Debug, Default)]
+pub struct ChildStdio {
+	/// The handle for writing to the child's stdin.
+	pub stdin: Option<ChildStdin>,
+
+	/// The handle for reading from the child's stdout.
+	pub stdout: Option<ChildStdout>,
+
+	/// The handle for reading from the child's stderr.
+	pub stderr: Option<ChildStderr>,
+}
+
 impl GroupChild {
 	#[cfg(unix)]
-	pub(crate) fn new(inner: Child) -> Self {
+	pub(crate) fn new(inner: Child, process_group: i32, kill_on_drop: Option<Signal>) -> Self {
 		Self {
-			imp: ChildImp::new(inner),
+			imp: ChildImp::new(inner, process_group, kill_on_drop),
 			exitstatus: None,
 		}
 	}
 
 	#[cfg(windows)]
-	pub(crate) fn new(inner: Child, j: HANDLE, c: HANDLE) -> Self {
+	pub(crate) fn new(inner: Child, handles: JobPort, new_process_group: bool) -> Self {
 		Self {
-			imp: ChildImp::new(inner, j, c),
+			imp: ChildImp::new(inner, handles, new_process_group),
 			exitstatus: None,
 		}
 	}
 
+	/// Wraps a child which was spawned some other way, such as by a dependency, to manage it as
+	/// a group.
+	///
+	/// This is best-effort, as the child has been running on its own until now. It can't be given
+	/// any of the builder's options, and processes it has already started stay out of the group.
+	///
+	/// On Unix, the child is moved into a new process group which it leads, with `setpgid()`. That's
+	/// only allowed until it runs its program, which the standard library has usually waited for
+	/// by the time `spawn()` returns. So in practice, this succeeds for a child which was already
+	/// made to lead its own group, for example with
+	/// [`CommandExt::process_group(0)`](std::os::unix::process::CommandExt::process_group), and
+	/// fails with `EACCES` for one which is still in this process's group: signalling that group
+	/// would reach this process too.
+	///
+	/// On Windows, the child is assigned to a new job object. Processes it starts from then on are
+	/// in the job too.
+	///
+	/// If this fails, the child is given back along with the error, still running and unchanged,
+	/// so it can be managed, or at least waited on, without the group.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// # #[cfg(unix)] {
+	/// use std::{os::unix::process::CommandExt, process::Command};
+	/// use command_group::GroupChild;
+	///
+	/// // spawned elsewhere
+	/// let child = Command::new("make").process_group(0).spawn().expect("make command didn't start");
+	///
+	/// let mut child = GroupChild::adopt(child).map_err(|(err, _)| err).expect("failed to adopt make");
+	/// child.wait().expect("make command wasn't running");
+	/// # }
+	/// ```
+	pub fn adopt(child: Child) -> std::result::Result<Self, (Error, Child)> {
+		ChildImp::adopt(child)
+			.map(|imp| Self {
+				imp,
+				exitstatus: None,
+			})
+			.map_err(|(err, child)| (Error::spawn(err), child))
+	}
+
 	/// Returns the stdlib [`Child`] object.
 	///
 	/// Note that the inner child may not be in the same state as this output child, due to how
@@ -104,6 +198,9 @@ impl GroupChild {
 	/// methods like `wait` and `kill` are implemented. It is not recommended to use this method
 	/// _after_ using any of the other methods on this struct.
 	///
+	/// If the group was spawned with `kill_on_drop`, this disarms it on Unix: the returned child is
+	/// not killed when dropped.
+	///
 	#[cfg_attr(
 		windows,
 		doc = "On Windows, this unnavoidably leaves a handle unclosed. Prefer [`inner()`](Self::inner)."
@@ -127,12 +224,76 @@ impl GroupChild {
 		self.imp.into_inner()
 	}
 
+	/// Lets the group carry on running on its own, and drops this handle to it.
+	///
+	/// This disarms `kill_on_drop`, so the group is neither signalled nor killed, now or when
+	/// this process exits. On Windows, the job object is told not to kill its processes when it's
+	/// closed, and its handles are then closed. The group's stdio handles are closed too, so
+	/// redirect them to files or [`Stdio::null()`](std::process::Stdio::null) if it should keep
+	/// writing output.
+	///
+	/// Unlike [`into_inner()`](Self::into_inner), which hands the leader over to be managed
+	/// through the standard library, this is for when nothing more is to be done with the group,
+	/// such as to start a daemon. On Unix, the group is no longer waited on, so its processes are
+	/// left as zombies once they exit, until this process exits too.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use std::process::{Command, Stdio};
+	/// use command_group::CommandGroup;
+	///
+	/// Command::new("my-daemon")
+	///         .stdout(Stdio::null())
+	///         .group()
+	///         .kill_on_drop(true)
+	///         .spawn()
+	///         .expect("my-daemon command didn't start")
+	///         .detach();
+	/// ```
+	pub fn detach(self) {
+		self.imp.detach();
+	}
+
+	/// Takes the stdio handles out of the child, so they can be owned separately from it.
+	///
+	/// The handles are then independent of what's done with the child: for example, reading from
+	/// stdout can carry on in another thread while this thread waits on the group. Methods which
+	/// use the handles, like [`wait_with_output()`](Self::wait_with_output), then see them as not
+	/// piped. Taking them again returns empty handles.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use std::io::Read;
+	/// use std::process::{Command, Stdio};
+	/// use command_group::CommandGroup;
+	///
+	/// let mut child = Command::new("ls").stdout(Stdio::piped()).group_spawn().expect("ls command didn't start");
+	/// let mut stdout = child.take_stdio().stdout.expect("stdout is piped");
+	/// let mut output = String::new();
+	/// stdout.read_to_string(&mut output).expect("failed to read from child");
+	/// child.wait().expect("command wasn't running");
+	/// ```
+	pub fn take_stdio(&mut self) -> ChildStdio {
+		ChildStdio {
+			stdin: self.imp.take_stdin(),
+			stdout: self.imp.take_stdout(),
+			stderr: self.imp.take_stderr(),
+		}
+	}
+
 	/// Forces the child process group to exit.
 	///
-	/// If the group has already exited, an [`InvalidInput`] error is returned.
+	/// If the group no longer exists, [`Error::AlreadyExited`] is returned. On Unix, that's once
+	/// every process in it has exited and been reaped; on Windows, once every process in the job
+	/// object has exited.
 	///
 	/// This is equivalent to sending a SIGKILL on Unix platforms.
 	///
+	/// Like the stdlib method, this does not wait for the group to exit, nor reap it: it is the
+	/// same as [`start_kill()`](Self::start_kill). Call [`wait()`](Self::wait) afterwards.
+	///
 	/// See [the stdlib documentation](Child::kill) for more.
 	///
 	/// # Examples
@@ -150,15 +311,21 @@ impl GroupChild {
 	///     println!("yes command didn't start");
 	/// }
 	/// ```
-	///
-	/// [`InvalidInput`]: std::io::ErrorKind::InvalidInput
 	pub fn kill(&mut self) -> Result<()> {
-		self.imp.kill()
+		self.imp.kill().map_err(Error::signal)
 	}
 
-	/// Returns the OS-assigned process group identifier.
+	/// Attempts to force the child group to exit, but does not wait for the request to take effect.
 	///
-	/// See [the stdlib documentation](Child::id) for more.
+	/// This is equivalent to sending a SIGKILL on Unix platforms, and to terminating the job object
+	/// on Windows.
+	///
+	/// This behaves exactly like [`kill()`](Self::kill), and exists for parity with
+	/// `AsyncGroupChild::start_kill`, whose `kill` also waits for the group to exit.
+	///
+	/// Note that on Unix platforms it is possible for a zombie process to remain after a kill is
+	/// sent; to avoid this, the caller should ensure that either `child.wait()` or
+	/// `child.try_wait()` is invoked successfully.
 	///
 	/// # Examples
 	///
@@ -168,21 +335,28 @@ impl GroupChild {
 	/// use std::process::Command;
 	/// use command_group::CommandGroup;
 	///
-	/// let mut command = Command::new("ls");
-	/// if let Ok(child) = command.group_spawn() {
-	///     println!("Child group's ID is {}", child.id());
+	/// let mut command = Command::new("yes");
+	/// if let Ok(mut child) = command.group_spawn() {
+	///     child.start_kill().expect("command wasn't running");
+	///     child.wait().expect("command didn't exit");
 	/// } else {
-	///     println!("ls command didn't start");
+	///     println!("yes command didn't start");
 	/// }
 	/// ```
-	pub fn id(&self) -> u32 {
-		self.imp.id()
+	pub fn start_kill(&mut self) -> Result<()> {
+		self.imp.kill().map_err(Error::signal)
 	}
 
-	/// Waits for the child group to exit completely, returning the status that
-	/// the process leader exited with.
+	/// Forces the child process group to exit, with the given exit code.
 	///
-	/// See [the stdlib documentation](Child::wait) for more.
+	/// This is the same as [`kill()`](Self::kill), which uses exit code 1, except that every
+	/// process in the job object exits with `code`. This can be used to tell a forced termination
+	/// apart from a process exiting with 1 on its own.
+	///
+	/// Waiting on the child afterwards reports `code` as the leader's exit code: its status is
+	/// only read once its process has been terminated, at which point the code is final. If the
+	/// leader had already exited on its own by the time of the kill, its own exit code is reported
+	/// instead.
 	///
 	/// # Examples
 	///
@@ -192,29 +366,59 @@ impl GroupChild {
 	/// use std::process::Command;
 	/// use command_group::CommandGroup;
 	///
-	/// let mut command = Command::new("ls");
+	/// let mut child = Command::new("cmd").arg("/C").arg("pause").group_spawn().unwrap();
+	/// child.kill_with_code(137).expect("command wasn't running");
+	/// let status = child.wait().expect("command didn't exit");
+	/// assert_eq!(status.code(), Some(137));
+	/// ```
+	#[cfg(windows)]
+	pub fn kill_with_code(&mut self, code: u32) -> Result<()> {
+		self.imp.kill_with_code(code).map_err(Error::signal)
+	}
+
+	/// Asks the child process group to exit.
+	///
+	/// On Unix, this sends SIGTERM to every process in the group.
+	///
+	/// On Windows, if the group was created with the `CREATE_NEW_PROCESS_GROUP` creation flag,
+	/// this sends a `CTRL_BREAK_EVENT` to the group's console. Otherwise, or if that fails, it
+	/// falls back to terminating the job object, like [`kill()`](Self::kill).
+	///
+	/// Unlike `kill`, processes may handle or ignore this request. Use [`wait()`](Self::wait) or
+	/// [`try_wait()`](Self::try_wait) to find out whether the group actually exited.
+	///
+	/// If the group no longer exists, this returns [`Error::AlreadyExited`], like `kill` does.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let mut command = Command::new("yes");
 	/// if let Ok(mut child) = command.group_spawn() {
-	///     child.wait().expect("command wasn't running");
-	///     println!("Child has finished its execution!");
+	///     child.terminate().expect("command wasn't running");
+	///     child.wait().expect("command didn't exit");
 	/// } else {
-	///     println!("ls command didn't start");
+	///     println!("yes command didn't start");
 	/// }
 	/// ```
-	pub fn wait(&mut self) -> Result<ExitStatus> {
-		if let Some(es) = self.exitstatus {
-			return Ok(es);
-		}
-
-		drop(self.imp.take_stdin());
-		let status = self.imp.wait()?;
-		self.exitstatus = Some(status);
-		Ok(status)
+	pub fn terminate(&mut self) -> Result<()> {
+		self.imp.terminate().map_err(Error::signal)
 	}
 
-	/// Attempts to collect the exit status of the child if it has already
-	/// exited.
+	/// Sends the given signal to the child process group.
 	///
-	/// See [the stdlib documentation](Child::try_wait) for more.
+	/// On Unix, this sends any signal to every process in the group, for example SIGQUIT or
+	/// SIGINT. [`kill()`](Self::kill) is the same as calling this with SIGKILL.
+	///
+	/// On Windows, this takes a [`Signal`](crate::Signal) defined by this crate instead, which
+	/// maps to killing the job object, [terminating](Self::terminate) the group, or sending it a
+	/// `CTRL_BREAK_EVENT`.
+	///
+	/// Like `kill()`, this does not wait for the group to exit.
 	///
 	/// # Examples
 	///
@@ -222,96 +426,1099 @@ impl GroupChild {
 	///
 	/// ```no_run
 	/// use std::process::Command;
-	/// use command_group::CommandGroup;
+	/// use command_group::{CommandGroup, Signal};
 	///
-	/// let mut child = Command::new("ls").group_spawn().unwrap();
+	/// let mut command = Command::new("yes");
+	/// if let Ok(mut child) = command.group_spawn() {
+	///     #[cfg(unix)]
+	///     child.kill_with(Signal::SIGQUIT).expect("command wasn't running");
+	///     #[cfg(windows)]
+	///     child.kill_with(Signal::Interrupt).expect("command wasn't running");
+	///     child.wait().expect("command didn't exit");
+	/// } else {
+	///     println!("yes command didn't start");
+	/// }
+	/// ```
+	pub fn kill_with(&mut self, sig: Signal) -> Result<()> {
+		self.imp.kill_with(sig).map_err(Error::signal)
+	}
+
+	/// Sends a signal to the group leader only, rather than to the whole group.
 	///
-	/// match child.try_wait() {
-	///     Ok(Some(status)) => println!("exited with: {}", status),
-	///     Ok(None) => {
-	///         println!("status not ready yet, let's really wait");
-	///         let res = child.wait();
-	///         println!("result: {:?}", res);
-	///     }
-	///     Err(e) => println!("error attempting to wait: {}", e),
+	/// [`UnixChildExt::signal()`] and [`kill_with()`](Self::kill_with) send signals to every
+	/// process in the group. This instead only signals the process which was spawned, for example
+	/// to ask a supervisor to reload its configuration without disturbing its workers, or to let a
+	/// shell handle SIGWINCH itself.
+	///
+	/// Returns [`Error::AlreadyExited`] if the leader has already been waited on, as its process
+	/// ID may since have been reused. See [Process ID reuse](Self#process-id-reuse) for how this
+	/// is detected.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::{CommandGroup, Signal};
+	///
+	/// let mut command = Command::new("bash");
+	/// if let Ok(mut child) = command.group_spawn() {
+	///     child.signal_leader(Signal::SIGWINCH).expect("command wasn't running");
+	/// } else {
+	///     println!("bash command didn't start");
 	/// }
 	/// ```
-	pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
-		if self.exitstatus.is_some() {
-			return Ok(self.exitstatus);
-		}
+	#[cfg(unix)]
+	pub fn signal_leader(&mut self, sig: Signal) -> Result<()> {
+		self.imp.signal_leader(sig).map_err(Error::signal)
+	}
 
-		match self.imp.try_wait()? {
-			Some(es) => {
-				self.exitstatus = Some(es);
-				Ok(Some(es))
-			}
-			None => Ok(None),
-		}
+	/// Sends a console control event to the group, like pressing Ctrl-C or Ctrl-Break in its
+	/// console.
+	///
+	/// This lets console programs exit gracefully, which [`kill()`](Self::kill) doesn't allow. It
+	/// does not wait for the group to exit.
+	///
+	/// This requires the group to have been spawned with
+	/// [`new_process_group`](crate::builder::CommandGroupBuilder::new_process_group), or the
+	/// `CREATE_NEW_PROCESS_GROUP` creation flag, and fails with [`Unsupported`] otherwise. Once
+	/// every process in the group has exited, this fails with [`Error::AlreadyExited`]. See
+	/// [`ControlEvent`](crate::ControlEvent) for caveats with Ctrl-C.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::{CommandGroup, ControlEvent};
+	///
+	/// let mut child = Command::new("ping")
+	///     .args(["-t", "localhost"])
+	///     .group()
+	///     .new_process_group(true)
+	///     .spawn()
+	///     .expect("ping command didn't start");
+	/// child.send_control(ControlEvent::CtrlBreak).expect("command wasn't running");
+	/// child.wait().expect("command didn't exit");
+	/// ```
+	///
+	/// [`Unsupported`]: std::io::ErrorKind::Unsupported
+	#[cfg(windows)]
+	pub fn send_control(&self, event: crate::ControlEvent) -> Result<()> {
+		self.imp.send_control(event).map_err(Error::signal)
 	}
 
-	/// Simultaneously waits for the child to exit and collect all remaining
-	/// output on the stdout/stderr handles, returning an `Output`
-	/// instance.
+	/// Sends a Ctrl-Break event to the group.
 	///
-	/// See [the stdlib documentation](Child::wait_with_output) for more.
+	/// This is the same as [`send_control()`](Self::send_control) with
+	/// [`ControlEvent::CtrlBreak`](crate::ControlEvent::CtrlBreak). Most console programs exit on
+	/// Ctrl-Break, and it's the only control event which can reliably be sent to the group alone.
+	#[cfg(windows)]
+	pub fn send_ctrl_break(&self) -> Result<()> {
+		self.send_control(crate::ControlEvent::CtrlBreak)
+	}
+
+	/// Sends a Ctrl-C event to the group.
 	///
-	/// # Bugs
+	/// This is the same as [`send_control()`](Self::send_control) with
+	/// [`ControlEvent::CtrlC`](crate::ControlEvent::CtrlC). Windows can't limit Ctrl-C to a single
+	/// process group, and processes in a new process group ignore it by default, so this is
+	/// unreliable; prefer [`send_ctrl_break()`](Self::send_ctrl_break).
+	#[cfg(windows)]
+	pub fn send_ctrl_c(&self) -> Result<()> {
+		self.send_control(crate::ControlEvent::CtrlC)
+	}
+
+	/// Returns a handle which can kill the group from another thread.
+	///
+	/// The handle doesn't borrow this child, and can be cloned and sent across threads. See
+	/// [`GroupKill`] for caveats, notably around process group ID reuse on Unix.
 	///
-	/// On Windows, STDOUT is read before STDERR if both are piped, which may block. This is mostly
-	/// because reading two outputs at the same time in synchronous code is horrendous. If you want
-	/// this, please contribute a better version. Alternatively, prefer using the async API.
+	/// On Windows, this duplicates the job object handle, which may fail.
 	///
 	/// # Examples
 	///
 	/// Basic usage:
 	///
-	/// ```should_panic
-	/// use std::process::{Command, Stdio};
+	/// ```no_run
+	/// use std::{process::Command, thread, time::Duration};
 	/// use command_group::CommandGroup;
 	///
-	/// let child = Command::new("/bin/cat")
-	///     .arg("file.txt")
-	///     .stdout(Stdio::piped())
-	///     .group_spawn()
-	///     .expect("failed to execute child");
+	/// let mut child = Command::new("yes").group_spawn().expect("yes command didn't start");
+	/// let killer = child.kill_handle().expect("failed to get a kill handle");
+	/// thread::spawn(move || {
+	///     thread::sleep(Duration::from_secs(1));
+	///     killer.kill().expect("command wasn't running");
+	/// });
+	/// child.wait().expect("command wasn't running");
+	/// ```
+	pub fn kill_handle(&self) -> Result<GroupKill> {
+		self.imp.kill_handle().map_err(Error::from)
+	}
+
+	/// Borrows the handle to the job object the group runs in.
 	///
-	/// let output = child
-	///     .wait_with_output()
-	///     .expect("failed to wait on child");
+	/// This can be used to query or configure the job object directly, for example with
+	/// `QueryInformationJobObject` or `SetInformationJobObject` on its raw handle, to apply limits
+	/// this crate doesn't provide.
 	///
-	/// assert!(output.status.success());
+	/// The handle is still owned by this child, and the borrow ensures it isn't used after the
+	/// child has been dropped. Do not close its raw handle: that would also terminate the group if
+	/// it was spawned with `kill_on_drop`. To keep a handle to the job around for longer, use
+	/// [`BorrowedHandle::try_clone_to_owned()`], which duplicates it. After
+	/// [`into_inner()`](Self::into_inner), the job handle is deliberately left open, and is
+	/// never closed.
+	///
+	/// The group is already running by the time this is available, so limits configured through
+	/// this race with the processes in it: anything they did or started before the limits were set
+	/// isn't affected. Prefer the builder's options for limits that must apply from the start.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let child = Command::new("cmd").arg("/C").arg("pause").group_spawn().unwrap();
+	/// let job = child.job_handle();
+	/// // e.g. call QueryInformationJobObject(job.as_raw_handle(), ...) here
 	/// ```
-	pub fn wait_with_output(mut self) -> Result<Output> {
-		drop(self.imp.take_stdin());
+	#[cfg(windows)]
+	pub fn job_handle(&self) -> BorrowedHandle<'_> {
+		self.imp.job_handle()
+	}
 
-		let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
-		match (self.imp.take_stdout(), self.imp.take_stderr()) {
-			(None, None) => {}
-			(Some(mut out), None) => {
-				out.read_to_end(&mut stdout)?;
-			}
-			(None, Some(mut err)) => {
-				err.read_to_end(&mut stderr)?;
-			}
-			(Some(out), Some(err)) => {
-				let res = ChildImp::read_both(out, &mut stdout, err, &mut stderr);
-				res.unwrap();
-			}
-		}
+	/// Borrows the handle to the I/O completion port which receives the job object's
+	/// notifications.
+	///
+	/// The same ownership rules apply as for [`job_handle()`](Self::job_handle), except that the
+	/// completion port is closed by `into_inner()`. This child reads from the completion port to
+	/// wait for the group to exit: dequeuing packets from it yourself will break waiting.
+	#[cfg(windows)]
+	pub fn completion_port_handle(&self) -> BorrowedHandle<'_> {
+		self.imp.completion_port_handle()
+	}
 
-		let status = self.imp.wait()?;
-		Ok(Output {
-			status,
-			stdout,
-			stderr,
-		})
+	/// Returns accounting information for the job object: CPU times, peak memory usage, and the
+	/// number of processes in the group.
+	///
+	/// This can be called at any time, including while the group is running and after it exited.
+	/// See [`JobAccounting`](crate::JobAccounting) for details.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let mut child = Command::new("cmd").arg("/C").arg("dir").group_spawn().unwrap();
+	/// child.wait().expect("command wasn't running");
+	/// let accounting = child.job_accounting().expect("failed to query job");
+	/// println!("used {:?} of CPU time", accounting.user_time + accounting.kernel_time);
+	/// ```
+	#[cfg(windows)]
+	pub fn job_accounting(&self) -> Result<crate::JobAccounting> {
+		self.imp.job_accounting().map_err(Error::from)
+	}
+
+	/// Returns the OS-assigned process identifier of the child, which is the group leader.
+	///
+	/// This is the same as [`leader_id()`](Self::leader_id). On Unix, this is usually also the
+	/// process group ID, as the child leads a new group. That isn't the case if it joined an
+	/// existing group with the builder's `process_group()`: use [`group_id()`](Self::group_id) to
+	/// get the group ID itself.
+	///
+	/// See [the stdlib documentation](Child::id) for more.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let mut command = Command::new("ls");
+	/// if let Ok(child) = command.group_spawn() {
+	///     println!("Child's ID is {}", child.id());
+	/// } else {
+	///     println!("ls command didn't start");
+	/// }
+	/// ```
+	pub fn id(&self) -> u32 {
+		self.imp.id()
+	}
+
+	/// Returns the OS-assigned process identifier of the child, or `None` once it has been reaped.
+	///
+	/// Once the leader's exit status has been collected, by [`wait()`](Self::wait),
+	/// [`try_wait()`](Self::try_wait) or similar, its process ID is freed and may be reused by
+	/// the OS for an unrelated process. Unlike [`id()`](Self::id), this stops returning the ID at
+	/// that point, so that it can't be mistaken for the child's. This is how Tokio's
+	/// `Child::id()` behaves.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let mut child = Command::new("ls").group_spawn().expect("ls command didn't start");
+	/// assert!(child.id_checked().is_some());
+	/// child.wait().expect("command wasn't running");
+	/// assert_eq!(child.id_checked(), None);
+	/// ```
+	pub fn id_checked(&self) -> Option<u32> {
+		if self.exitstatus.is_some() {
+			None
+		} else {
+			Some(self.id())
+		}
+	}
+
+	/// Returns the process group ID which signals are sent to.
+	///
+	/// This is the same as [`id()`](Self::id), unless the child joined an existing group with the
+	/// builder's `process_group()`.
+	///
+	/// On Windows, there are no process group IDs: the group is a job object, which has no ID.
+	/// This returns the leader's process ID instead, like `id()`.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let mut command = Command::new("ls");
+	/// if let Ok(child) = command.group_spawn() {
+	///     println!("Child group's ID is {}", child.group_id());
+	/// } else {
+	///     println!("ls command didn't start");
+	/// }
+	/// ```
+	pub fn group_id(&self) -> u32 {
+		self.imp.group_id()
+	}
+
+	/// Returns the process ID of the group leader, the process which was spawned.
+	///
+	/// This is the same as [`id()`](Self::id), and exists to make it explicit which of the leader
+	/// and the group is meant, next to [`group_id()`](Self::group_id).
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let mut command = Command::new("ls");
+	/// if let Ok(child) = command.group_spawn() {
+	///     println!("Child group's leader is {}", child.leader_id());
+	/// } else {
+	///     println!("ls command didn't start");
+	/// }
+	/// ```
+	pub fn leader_id(&self) -> u32 {
+		self.imp.id()
+	}
+
+	/// Returns a pidfd for the group leader, if the kernel supports them (Linux 5.3 and later).
+	///
+	/// The pidfd is opened when the group is spawned. It becomes readable when the _leader_
+	/// exits, not when the whole group has: other processes in the group may still be running
+	/// then. This can be registered with `poll`, `epoll`, or an event loop, and once it's
+	/// readable, [`try_wait_group()`](Self::try_wait_group) can be called to reap the group.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	/// use nix::poll::{poll, PollFd, PollFlags};
+	///
+	/// let mut child = Command::new("ls").group_spawn().unwrap();
+	/// let fd = child.pidfd().expect("pidfds are not supported");
+	/// poll(&mut [PollFd::new(&fd, PollFlags::POLLIN)], -1).expect("failed to poll");
+	/// let status = child.wait().expect("command wasn't running");
+	/// ```
+	#[cfg(target_os = "linux")]
+	pub fn pidfd(&self) -> Option<BorrowedFd<'_>> {
+		self.imp.pidfd()
+	}
+
+	/// Returns how many processes are currently in the group.
+	///
+	/// This includes the leader while it's running, and is 0 once every process in the group has
+	/// exited. For example, this can be used to wait until a daemon's workers have all exited.
+	///
+	/// On Windows, this is the number of active processes in the job object.
+	///
+	/// On Unix, this counts the processes whose process group ID is the group's. If the child
+	/// joined an existing group with the builder's `process_group()`, that includes processes
+	/// which were already in it. Once the group is gone, its ID may be reused by an unrelated
+	/// group, which would then be counted. On Linux, this reads `/proc`, and doesn't count
+	/// zombies. On macOS, zombies are counted. On other platforms, this returns an
+	/// [`Unsupported`](std::io::ErrorKind::Unsupported) error.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let child = Command::new("sh").arg("-c").arg("sleep 10 & sleep 10 &").group_spawn().unwrap();
+	/// println!("{} processes running", child.process_count().expect("failed to count"));
+	/// ```
+	pub fn process_count(&self) -> Result<usize> {
+		self.imp.process_count().map_err(Error::from)
+	}
+
+	/// Returns the process IDs of the processes currently in the group, in ascending order.
+	///
+	/// This includes the leader while it's running, and can help find out which lingering member
+	/// is keeping a group from exiting. It is a snapshot: processes may have exited or been started
+	/// by the time it's returned. The same platform differences as for
+	/// [`process_count()`](Self::process_count) apply: on Windows, this lists the processes in the
+	/// job object; on Unix, those with the group's process group ID, which is only supported on
+	/// Linux and macOS.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let child = Command::new("sh").arg("-c").arg("sleep 10 & sleep 10 &").group_spawn().unwrap();
+	/// for pid in child.pids().expect("failed to list processes") {
+	///     println!("{pid}");
+	/// }
+	/// ```
+	pub fn pids(&self) -> Result<Vec<u32>> {
+		let mut pids = self.imp.pids()?;
+		pids.sort_unstable();
+		Ok(pids)
+	}
+
+	/// Waits for the child group to exit completely, returning the status that
+	/// the process leader exited with.
+	///
+	/// See [the stdlib documentation](Child::wait) for more.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let mut command = Command::new("ls");
+	/// if let Ok(mut child) = command.group_spawn() {
+	///     child.wait().expect("command wasn't running");
+	///     println!("Child has finished its execution!");
+	/// } else {
+	///     println!("ls command didn't start");
+	/// }
+	/// ```
+	pub fn wait(&mut self) -> Result<ExitStatus> {
+		if let Some(es) = self.exitstatus {
+			return Ok(es);
+		}
+
+		drop(self.imp.take_stdin());
+		let status = self.imp.wait().map_err(Error::wait)?;
+		self.exitstatus = Some(status);
+		Ok(status)
+	}
+
+	/// Makes the group the foreground process group of the terminal.
+	///
+	/// Processes which aren't in the foreground group of their controlling terminal are stopped
+	/// with `SIGTTIN` when they try to read from it, and don't receive the signals sent by keys
+	/// like Ctrl-C. This hands the terminal over to the group, as a shell does when running a
+	/// command or resuming a job with `fg`.
+	///
+	/// This calls `tcsetpgrp()` on our controlling terminal, with `SIGTTOU` blocked so that it
+	/// also works when called from a background group. It fails if there's no controlling
+	/// terminal, or if the group is in another session. Our own group is then in the background:
+	/// to take the terminal back, call `tcsetpgrp()` with our own process group ID.
+	///
+	/// The group can also be put in the foreground from the start, with the builder's
+	/// [`foreground()`](crate::builder::CommandGroupBuilder::foreground) option.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let mut child = Command::new("vim").group_spawn().unwrap();
+	/// child.set_foreground().expect("no controlling terminal");
+	/// child.wait().expect("command wasn't running");
+	/// ```
+	#[cfg(unix)]
+	pub fn set_foreground(&self) -> Result<()> {
+		crate::tty::set_foreground(self.imp.group_id() as _).map_err(Error::from)
+	}
+
+	/// Waits for the group leader to change state, returning how it did.
+	///
+	/// Unlike [`wait()`](Self::wait), this also returns when the leader is stopped or continued,
+	/// for example by `SIGTSTP` and `SIGCONT`, which lets job control tools keep track of whether
+	/// the group is running in the foreground or has been suspended. Stopped and continued states
+	/// are those of the leader only: other processes in the group may change state independently.
+	///
+	/// Once the leader has terminated, this reaps the group like `wait()` does, and keeps returning
+	/// the same [`GroupStatus::Exited`] or [`GroupStatus::Signaled`] afterwards. The stdin handle
+	/// is left open until then, as the group may still be resumed.
+	///
+	/// If the leader was terminated by a signal that [`Signal`] doesn't know of, such as a realtime
+	/// signal, this returns an error; the exit status is then still available from `wait()`.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::{CommandGroup, GroupStatus};
+	///
+	/// let mut child = Command::new("vim").group_spawn().unwrap();
+	/// loop {
+	///     match child.wait_status().expect("command wasn't running") {
+	///         GroupStatus::Stopped(sig) => println!("stopped by {sig}"),
+	///         GroupStatus::Continued => println!("continued"),
+	///         status => {
+	///             println!("finished: {status:?}");
+	///             break;
+	///         }
+	///     }
+	/// }
+	/// ```
+	#[cfg(unix)]
+	pub fn wait_status(&mut self) -> Result<GroupStatus> {
+		if self.exitstatus.is_none() {
+			if let Some(status) = self.imp.wait_stopped().map_err(Error::wait)? {
+				return Ok(status);
+			}
+		}
+
+		Ok(GroupStatus::from_exit(self.wait()?)?)
+	}
+
+	/// Waits for the child group to exit completely, returning the pid and exit status of every
+	/// process that was reaped, including the process leader.
+	///
+	/// Only processes which are direct children of this process can be reaped: when the leader
+	/// exits, its own children are reparented to init (or the nearest subreaper), and won't
+	/// appear here. In practice, this mostly reports on the leader, plus any other processes that
+	/// were moved into the group and are children of this process.
+	///
+	/// Unlike [`wait()`](Self::wait), this does not return as soon as the leader has exited, but
+	/// waits until there's nothing left to reap. The leader's status is then cached, so a later
+	/// `wait()` returns it without reaping again.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let mut child = Command::new("ls").group_spawn().unwrap();
+	/// for (pid, status) in child.wait_all().expect("command wasn't running") {
+	///     println!("{} exited with: {}", pid, status);
+	/// }
+	/// ```
+	#[cfg(unix)]
+	pub fn wait_all(&mut self) -> Result<Vec<(u32, ExitStatus)>> {
+		drop(self.imp.take_stdin());
+		let all = self.imp.wait_all().map_err(Error::wait)?;
+		self.exitstatus = Some(self.imp.wait().map_err(Error::wait)?);
+		Ok(all)
+	}
+
+	/// Waits for the child group to exit completely, returning the worst status of any process
+	/// that was reaped, like a shell pipeline with `pipefail`.
+	///
+	/// This waits like [`wait_all()`](Self::wait_all), and returns the leader's status if it failed,
+	/// that is if it exited with a non-zero code or was killed by a signal. Otherwise, this returns
+	/// the status of the first other process to have failed, if any. If everything succeeded, this
+	/// is the leader's successful status.
+	///
+	/// The same limits as for `wait_all()` apply: only processes which are children of this one
+	/// can be reaped, so the children of the leader, such as the commands a shell runs, aren't
+	/// accounted for. This is opt-in: [`wait()`](Self::wait) and the cached status that it and
+	/// [`try_wait()`](Self::try_wait) return are still only the leader's.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let mut child = Command::new("ls").group_spawn().unwrap();
+	/// let status = child.wait_worst().expect("command wasn't running");
+	/// println!("the group exited with: {}", status);
+	/// ```
+	#[cfg(unix)]
+	pub fn wait_worst(&mut self) -> Result<ExitStatus> {
+		let all = self.wait_all()?;
+		let leader = self.exitstatus.expect("set by wait_all");
+		if !leader.success() {
+			return Ok(leader);
+		}
+
+		Ok(all
+			.into_iter()
+			.map(|(_, status)| status)
+			.find(|status| !status.success())
+			.unwrap_or(leader))
+	}
+
+	/// Waits for the child group to exit completely, returning the status that the process leader
+	/// exited with, and the resource usage of the group.
+	///
+	/// Like [`wait_all()`](Self::wait_all), this waits until there's nothing left to reap. The CPU
+	/// times of every reaped process are summed, including those of the processes they waited on
+	/// themselves; see [`Rusage`](crate::Rusage) for details. Processes which were reaped before
+	/// this is called, for example by an earlier `try_wait()`, are also included.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let mut child = Command::new("ls").group_spawn().unwrap();
+	/// let (status, usage) = child.wait_with_rusage().expect("command wasn't running");
+	/// println!("exited with {} after {:?} of CPU time", status, usage.user_time + usage.system_time);
+	/// ```
+	#[cfg(unix)]
+	pub fn wait_with_rusage(&mut self) -> Result<(ExitStatus, crate::Rusage)> {
+		drop(self.imp.take_stdin());
+		let (status, usage) = self.imp.wait_with_rusage().map_err(Error::wait)?;
+		self.exitstatus = Some(status);
+		Ok((status, usage))
+	}
+
+	/// Attempts to collect the exit status of the child if it has already
+	/// exited.
+	///
+	/// See [the stdlib documentation](Child::try_wait) for more.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::Command;
+	/// use command_group::CommandGroup;
+	///
+	/// let mut child = Command::new("ls").group_spawn().unwrap();
+	///
+	/// match child.try_wait() {
+	///     Ok(Some(status)) => println!("exited with: {}", status),
+	///     Ok(None) => {
+	///         println!("status not ready yet, let's really wait");
+	///         let res = child.wait();
+	///         println!("result: {:?}", res);
+	///     }
+	///     Err(e) => println!("error attempting to wait: {}", e),
+	/// }
+	/// ```
+	pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		if self.exitstatus.is_some() {
+			return Ok(self.exitstatus);
+		}
+
+		match self.imp.try_wait().map_err(Error::wait)? {
+			Some(es) => {
+				self.exitstatus = Some(es);
+				Ok(Some(es))
+			}
+			None => Ok(None),
+		}
+	}
+
+	/// Reaps what it can of the group without blocking, returning the status that the process
+	/// leader exited with once the whole group has exited.
+	///
+	/// Unlike [`try_wait()`](Self::try_wait), which returns as soon as the leader has exited, this
+	/// keeps returning `None` while any process is left in the group. It's meant to be called
+	/// repeatedly from an event loop, for example once the leader's
+	#[cfg_attr(target_os = "linux", doc = "[`pidfd()`](Self::pidfd)")]
+	#[cfg_attr(not(target_os = "linux"), doc = "pidfd")]
+	/// has become readable. Once a status has been collected, it is cached as with
+	/// [`wait()`](Self::wait).
+	///
+	/// On Unix, only processes which are children of this one can be reaped. If the group leader
+	/// left any of its own children running, they are not waited for.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::{process::Command, thread::sleep, time::Duration};
+	/// use command_group::CommandGroup;
+	///
+	/// let mut child = Command::new("ls").group_spawn().unwrap();
+	/// while child.try_wait_group().expect("error attempting to wait").is_none() {
+	///     sleep(Duration::from_millis(10));
+	/// }
+	/// ```
+	pub fn try_wait_group(&mut self) -> Result<Option<ExitStatus>> {
+		let status = self.imp.try_wait_group().map_err(Error::wait)?;
+		if status.is_some() {
+			self.exitstatus = status;
+		}
+		Ok(status)
+	}
+
+	/// Waits for the child to exit, but gives up after `timeout`.
+	///
+	/// Returns `Ok(None)` if the child has not exited by then. Once an exit status has been
+	/// collected, it is cached in the same way as with [`wait()`](Self::wait), so further calls to
+	/// this method or to `wait` and [`try_wait`](Self::try_wait) return the same status.
+	///
+	/// Unlike `wait`, this does not close the child's stdin, so it may be called repeatedly while
+	/// still interacting with the child.
+	///
+	/// On Unix, like [`try_wait`](Self::try_wait), this returns as soon as the leader has exited,
+	/// even if other processes in the group are still running; use
+	/// [`try_wait_group()`](Self::try_wait_group) to check for those. It polls for the leader's
+	/// exit at a short interval until the timeout elapses, or on Linux 5.3 and later, sleeps until
+	/// the leader exits. On Windows, it waits for every process in the job to exit.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::{process::Command, time::Duration};
+	/// use command_group::CommandGroup;
+	///
+	/// let mut child = Command::new("yes").group_spawn().unwrap();
+	///
+	/// match child.wait_timeout(Duration::from_secs(1)) {
+	///     Ok(Some(status)) => println!("exited with: {}", status),
+	///     Ok(None) => {
+	///         println!("still running after a second, killing it");
+	///         child.kill().unwrap();
+	///     }
+	///     Err(e) => println!("error attempting to wait: {}", e),
+	/// }
+	/// ```
+	pub fn wait_timeout(&mut self, timeout: Duration) -> Result<Option<ExitStatus>> {
+		if self.exitstatus.is_some() {
+			return Ok(self.exitstatus);
+		}
+
+		match self.imp.wait_timeout(timeout).map_err(Error::wait)? {
+			Some(es) => {
+				self.exitstatus = Some(es);
+				Ok(Some(es))
+			}
+			None => Ok(None),
+		}
+	}
+
+	/// Waits for the child group to exit for up to `timeout`, killing it if it hasn't by then.
+	///
+	/// Returns `Ok(Some(status))` with the status that the process leader exited with if the group
+	/// finished in time, or `Ok(None)` if it had to be [`kill()`](Self::kill)ed. In that case, the
+	/// group has been waited on before returning, and its status is available from
+	/// [`wait()`](Self::wait).
+	///
+	/// Like `wait`, this closes the child's stdin first, so a group waiting for input isn't left
+	/// to time out. The timeout works as in [`wait_timeout()`](Self::wait_timeout). To ask the
+	/// group to exit before forcing it, see [`terminate_then_kill()`](Self::terminate_then_kill).
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::{process::Command, time::Duration};
+	/// use command_group::CommandGroup;
+	///
+	/// let mut child = Command::new("make").arg("test").group_spawn().unwrap();
+	/// match child.wait_or_kill(Duration::from_secs(60)).expect("failed to wait for command") {
+	///     Some(status) => println!("exited with: {}", status),
+	///     None => println!("timed out"),
+	/// }
+	/// ```
+	pub fn wait_or_kill(&mut self, timeout: Duration) -> Result<Option<ExitStatus>> {
+		drop(self.imp.take_stdin());
+		if let Some(es) = self.wait_timeout(timeout)? {
+			return Ok(Some(es));
+		}
+
+		ignore_exited(self.kill())?;
+		self.wait()?;
+		Ok(None)
+	}
+
+	/// Asks the child group to exit, then forces it to if it hasn't done so within `grace`.
+	///
+	/// This first calls [`terminate()`](Self::terminate) and waits up to `grace` for the group to
+	/// exit. If it's still running after that, it is [`kill()`](Self::kill)ed and waited on. If the
+	/// group exits during the grace period, it is not killed.
+	///
+	/// Returns the status that the process leader exited with, either way. If the group had
+	/// already exited before this was called, that status is returned without signalling it.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::{process::Command, time::Duration};
+	/// use command_group::CommandGroup;
+	///
+	/// let mut child = Command::new("yes").group_spawn().unwrap();
+	/// let status = child.terminate_then_kill(Duration::from_secs(5)).expect("failed to stop command");
+	/// println!("exited with: {}", status);
+	/// ```
+	pub fn terminate_then_kill(&mut self, grace: Duration) -> Result<ExitStatus> {
+		if let Some(es) = self.try_wait()? {
+			return Ok(es);
+		}
+
+		ignore_exited(self.terminate())?;
+		if let Some(es) = self.wait_timeout(grace)? {
+			return Ok(es);
+		}
+
+		ignore_exited(self.kill())?;
+		self.wait()
+	}
+
+	/// Simultaneously waits for the child to exit and collect all remaining
+	/// output on the stdout/stderr handles, returning an `Output`
+	/// instance.
+	///
+	/// See [the stdlib documentation](Child::wait_with_output) for more.
+	///
+	/// If both STDOUT and STDERR are piped, they are read concurrently, so that the child can't
+	/// block on either of them filling up. On Windows, this uses a thread to read STDERR.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```should_panic
+	/// use std::process::{Command, Stdio};
+	/// use command_group::CommandGroup;
+	///
+	/// let child = Command::new("/bin/cat")
+	///     .arg("file.txt")
+	///     .stdout(Stdio::piped())
+	///     .group_spawn()
+	///     .expect("failed to execute child");
+	///
+	/// let output = child
+	///     .wait_with_output()
+	///     .expect("failed to wait on child");
+	///
+	/// assert!(output.status.success());
+	/// ```
+	pub fn wait_with_output(mut self) -> Result<Output> {
+		drop(self.imp.take_stdin());
+
+		let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+		match (self.imp.take_stdout(), self.imp.take_stderr()) {
+			(None, None) => {}
+			(Some(mut out), None) => {
+				out.read_to_end(&mut stdout)?;
+			}
+			(None, Some(mut err)) => {
+				err.read_to_end(&mut stderr)?;
+			}
+			(Some(out), Some(err)) => {
+				ChildImp::read_both(out, &mut stdout, err, &mut stderr)?;
+			}
+		}
+
+		let status = self.imp.wait().map_err(Error::wait)?;
+		Ok(Output {
+			status,
+			stdout,
+			stderr,
+		})
+	}
+
+	/// Writes `input` to the child's stdin and closes it, while collecting all output and waiting
+	/// for the child to exit, like [`wait_with_output()`](Self::wait_with_output).
+	///
+	/// The input is written on another thread, so that the child can't block writing its output
+	/// while this is blocked writing its input. If the child exits or closes its stdin before
+	/// reading all of the input, the rest is discarded.
+	///
+	/// STDIN must be piped, unless `input` is empty. STDOUT and STDERR are collected if piped.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::{Command, Stdio};
+	/// use command_group::CommandGroup;
+	///
+	/// let child = Command::new("sort")
+	///     .stdin(Stdio::piped())
+	///     .stdout(Stdio::piped())
+	///     .group_spawn()
+	///     .expect("failed to execute child");
+	///
+	/// let output = child
+	///     .communicate(b"b\na\n")
+	///     .expect("failed to communicate with child");
+	///
+	/// assert_eq!(output.stdout, b"a\nb\n");
+	/// ```
+	pub fn communicate(mut self, input: &[u8]) -> Result<Output> {
+		let stdin = self.imp.take_stdin();
+		if stdin.is_none() && !input.is_empty() {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"the child's stdin is not piped",
+			)
+			.into());
+		}
+
+		thread::scope(|s| {
+			let writer =
+				stdin.map(|mut stdin| s.spawn(move || ignore_broken_pipe(stdin.write_all(input))));
+			let output = self.wait_with_output()?;
+			if let Some(writer) = writer {
+				writer
+					.join()
+					.unwrap_or_else(|_| Err(io::Error::other("stdin writer panicked")))?;
+			}
+
+			Ok(output)
+		})
+	}
+
+	/// Waits for the child to exit, passing each line of its output to `f` as it's read.
+	///
+	/// This is like [`wait_with_output()`](Self::wait_with_output), but rather than collecting the
+	/// output until the child exits, it's handed over line by line. Each [`OutputLine`] records
+	/// whether it came from STDOUT or STDERR. Lines from the two streams are passed in the order
+	/// they're read, and a partial line is held on to until it's completed, or until its stream is
+	/// closed. Only piped streams are read.
+	///
+	/// Like `wait_with_output`, this closes the child's stdin first, and returns once the output
+	/// has closed and the whole group has exited.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::{Command, Stdio};
+	/// use command_group::{CommandGroup, OutputSource};
+	///
+	/// let child = Command::new("cargo")
+	///     .arg("build")
+	///     .stdout(Stdio::piped())
+	///     .stderr(Stdio::piped())
+	///     .group_spawn()
+	///     .expect("cargo command didn't start");
+	///
+	/// let status = child
+	///     .stream_output(|output| {
+	///         let line = String::from_utf8_lossy(&output.line);
+	///         match output.source {
+	///             OutputSource::Stdout => println!("out: {line}"),
+	///             OutputSource::Stderr => println!("err: {line}"),
+	///         }
+	///     })
+	///     .expect("failed to wait on child");
+	///
+	/// assert!(status.success());
+	/// ```
+	pub fn stream_output<F: FnMut(OutputLine)>(mut self, mut f: F) -> Result<ExitStatus> {
+		drop(self.imp.take_stdin());
+
+		let mut stdout = LineSplitter::new(OutputSource::Stdout);
+		let mut stderr = LineSplitter::new(OutputSource::Stderr);
+		let mut push = |source, data: &[u8]| match source {
+			OutputSource::Stdout => stdout.push(data, &mut f),
+			OutputSource::Stderr => stderr.push(data, &mut f),
+		};
+		match (self.imp.take_stdout(), self.imp.take_stderr()) {
+			(None, None) => {}
+			(Some(out), None) => read_chunks(out, |data| push(OutputSource::Stdout, data))?,
+			(None, Some(err)) => read_chunks(err, |data| push(OutputSource::Stderr, data))?,
+			(Some(out), Some(err)) => ChildImp::stream_both(out, err, push)?,
+		}
+		stdout.finish(&mut f);
+		stderr.finish(&mut f);
+
+		self.imp.wait().map_err(Error::wait)
+	}
+
+	/// Like [`wait_with_output()`](Self::wait_with_output), but stops collecting each of STDOUT
+	/// and STDERR once it reaches its size limit, to bound memory use.
+	///
+	/// Past its limit, a stream is still read until it closes, but what's read is discarded, so the
+	/// child doesn't block on a full pipe. The returned [`LimitedOutput`] records which streams
+	/// were cut short. As with `wait_with_output`, this waits for the streams to close and the
+	/// group to exit, so a group which never stops writing has to be stopped some other way, such
+	/// as with a [`kill_handle()`](Self::kill_handle).
+	///
+	/// If both STDOUT and STDERR are piped, STDERR is read on another thread.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// use std::process::{Command, Stdio};
+	/// use command_group::CommandGroup;
+	///
+	/// let child = Command::new("cat")
+	///     .arg("big.log")
+	///     .stdout(Stdio::piped())
+	///     .group_spawn()
+	///     .expect("failed to execute child");
+	///
+	/// let limited = child
+	///     .wait_with_output_limited(1024, 1024)
+	///     .expect("failed to wait on child");
+	///
+	/// if limited.stdout_truncated {
+	///     println!("only showing the first KiB of the log");
+	/// }
+	/// assert!(limited.output.stdout.len() <= 1024);
+	/// ```
+	pub fn wait_with_output_limited(
+		mut self,
+		max_stdout: usize,
+		max_stderr: usize,
+	) -> Result<LimitedOutput> {
+		drop(self.imp.take_stdin());
+
+		let ((stdout, stdout_truncated), (stderr, stderr_truncated)) =
+			match (self.imp.take_stdout(), self.imp.take_stderr()) {
+				(None, None) => Default::default(),
+				(Some(out), None) => (read_limited(out, max_stdout)?, Default::default()),
+				(None, Some(err)) => (Default::default(), read_limited(err, max_stderr)?),
+				(Some(out), Some(err)) => thread::scope(|s| {
+					let err_thread = s.spawn(|| read_limited(err, max_stderr));
+					let stdout = read_limited(out, max_stdout);
+					let stderr = err_thread
+						.join()
+						.unwrap_or_else(|_| Err(io::Error::other("stderr reader panicked")));
+					Ok::<_, io::Error>((stdout?, stderr?))
+				})?,
+			};
+
+		let status = self.wait()?;
+		Ok(LimitedOutput {
+			output: Output {
+				status,
+				stdout,
+				stderr,
+			},
+			stdout_truncated,
+			stderr_truncated,
+		})
+	}
+}
+
+/// Reads a stream to its end, passing what's read to `f` as it arrives.
+fn read_chunks(mut stream: impl Read, mut f: impl FnMut(&[u8])) -> io::Result<()> {
+	let mut buf = [0; 8192];
+	loop {
+		match stream.read(&mut buf) {
+			Ok(0) => return Ok(()),
+			Ok(n) => f(&buf[..n]),
+			Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+			Err(err) => return Err(err),
+		}
+	}
+}
+
+/// Reads a stream to its end, keeping at most `max` bytes, and whether any more were discarded.
+fn read_limited(mut stream: impl Read, max: usize) -> io::Result<(Vec<u8>, bool)> {
+	let mut data = Vec::new();
+	let mut truncated = false;
+	let mut buf = [0; 8192];
+	loop {
+		let n = match stream.read(&mut buf) {
+			Ok(0) => return Ok((data, truncated)),
+			Ok(n) => n,
+			Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+			Err(err) => return Err(err),
+		};
+
+		let take = n.min(max - data.len());
+		data.extend_from_slice(&buf[..take]);
+		truncated |= take < n;
+	}
+}
+
+/// Reads from the leader's stdout.
+///
+/// This is a shorthand for reading from `inner().stdout`, for when that's the only stream of
+/// interest. It returns an [`InvalidInput`](io::ErrorKind::InvalidInput) error if stdout wasn't
+/// piped, or has been taken out of the child.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::Read;
+/// use std::process::{Command, Stdio};
+/// use command_group::CommandGroup;
+///
+/// let mut child = Command::new("ls").stdout(Stdio::piped()).group_spawn().expect("ls command didn't start");
+/// let mut output = String::new();
+/// child.read_to_string(&mut output).expect("failed to read from child");
+/// child.wait().expect("command wasn't running");
+/// ```
+impl Read for GroupChild {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		match &mut self.inner().stdout {
+			Some(stdout) => stdout.read(buf),
+			None => Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"the child's stdout is not piped",
+			)),
+		}
 	}
 }
 
 #[cfg(unix)]
 impl UnixChildExt for GroupChild {
 	fn signal(&self, sig: Signal) -> Result<()> {
-		self.imp.signal_imp(sig)
+		self.imp.signal_imp(sig).map_err(Error::signal)
 	}
 }
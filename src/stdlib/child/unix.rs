@@ -1,6 +1,7 @@
 use std::{
 	convert::TryInto,
-	io::{Error, Read, Result},
+	io::{Error, ErrorKind, Read, Result},
+	mem,
 	os::{
 		fd::BorrowedFd,
 		unix::{
@@ -9,32 +10,117 @@ use std::{
 		},
 	},
 	process::{Child, ChildStderr, ChildStdin, ChildStdout, ExitStatus},
+	thread::sleep,
+	time::{Duration, Instant},
 };
 
+#[cfg(target_os = "linux")]
+use crate::pidfd::{pidfd_open, pidfd_send_signal};
+#[cfg(target_os = "linux")]
+use std::os::fd::{AsFd, OwnedFd};
+
+use super::read_chunks;
+use crate::{error::kill_error, procs::group_pids, GroupKill, GroupStatus, OutputSource, Rusage};
 use nix::{
 	errno::Errno,
 	libc,
 	poll::{poll, PollFd, PollFlags},
 	sys::{
-		signal::{killpg, Signal},
-		wait::WaitPidFlag,
+		signal::{kill, killpg, Signal},
+		wait::{waitpid, WaitPidFlag},
 	},
-	unistd::Pid,
+	unistd::{getpgid, setpgid, Pid},
 };
 
 pub(super) struct ChildImp {
 	pgid: Pid,
+	leader: Pid,
 	inner: Child,
+	kill_on_drop: Option<KillOnDrop>,
+	reaped: Vec<(Pid, ExitStatus)>,
+	leader_exit: Option<ExitStatus>,
+	rusage: Rusage,
+	#[cfg(target_os = "linux")]
+	pidfd: Option<OwnedFd>,
+}
+
+/// Signals the process group when dropped, then reaps those of its processes which have already
+/// exited, without waiting for the others: blocking in drop could stall the thread.
+///
+/// This is disarmed once the group leader has been reaped: past that point the process group ID
+/// may be reused by the kernel, and the group is most likely gone anyway.
+struct KillOnDrop {
+	pgid: Pid,
+	signal: Signal,
+	wait_target: Pid,
+}
+
+impl KillOnDrop {
+	fn disarm(self) {
+		mem::forget(self);
+	}
+}
+
+impl Drop for KillOnDrop {
+	fn drop(&mut self) {
+		// nothing useful can be done with errors here
+		if killpg(self.pgid, self.signal).is_err() {
+			return;
+		}
+
+		// processes which are still exiting are left as zombies until we exit, like with std
+		while let Ok(status) = waitpid(self.wait_target, Some(WaitPidFlag::WNOHANG)) {
+			if status.pid().is_none() {
+				break;
+			}
+		}
+	}
 }
 
 impl ChildImp {
-	pub(super) fn new(inner: Child) -> Self {
+	pub(super) fn new(inner: Child, process_group: i32, kill_on_drop: Option<Signal>) -> Self {
+		let leader = Pid::from_raw(inner.id().try_into().expect("Command PID > i32::MAX"));
+		let pgid = if process_group == 0 {
+			leader
+		} else {
+			Pid::from_raw(process_group)
+		};
+		// opened before the leader can be reaped, so it can't refer to another process
+		#[cfg(target_os = "linux")]
+		let pidfd = pidfd_open(inner.id());
 		Self {
-			pgid: Pid::from_raw(inner.id().try_into().expect("Command PID > i32::MAX")),
+			pgid,
+			leader,
 			inner,
+			kill_on_drop: kill_on_drop.map(|signal| KillOnDrop {
+				pgid,
+				signal,
+				wait_target: wait_target(pgid, leader),
+			}),
+			reaped: Vec::new(),
+			leader_exit: None,
+			rusage: Rusage::default(),
+			#[cfg(target_os = "linux")]
+			pidfd,
 		}
 	}
 
+	/// Moves an already-spawned child into a new process group which it leads.
+	///
+	/// On failure, the child is given back along with the error.
+	pub(super) fn adopt(inner: Child) -> std::result::Result<Self, (Error, Child)> {
+		let leader = Pid::from_raw(inner.id().try_into().expect("Command PID > i32::MAX"));
+		if let Err(errno) = setpgid(leader, leader) {
+			// Too late to move it, typically because it has already run its program: it can
+			// still be adopted if it leads a group of its own, but not if it's in ours.
+			if getpgid(Some(leader)) != Ok(leader) {
+				return Err((Error::from(errno), inner));
+			}
+		}
+
+		Ok(Self::new(inner, 0, None))
+	}
+
 	pub(super) fn take_stdin(&mut self) -> Option<ChildStdin> {
 		self.inner.stdin.take()
 	}
@@ -51,50 +137,121 @@ impl ChildImp {
 		&mut self.inner
 	}
 
-	pub fn into_inner(self) -> Child {
+	pub(super) fn inner_ref(&self) -> &Child {
+		&self.inner
+	}
+
+	pub fn into_inner(mut self) -> Child {
+		// the caller takes over responsibility for the process
+		self.disarm_kill_on_drop();
 		self.inner
 	}
 
+	pub fn detach(self) {
+		// the Child doesn't do anything to the process when dropped
+		drop(self.into_inner());
+	}
+
+	fn disarm_kill_on_drop(&mut self) {
+		if let Some(guard) = self.kill_on_drop.take() {
+			guard.disarm();
+		}
+	}
+
+	pub(super) fn kill_handle(&self) -> Result<GroupKill> {
+		Ok(GroupKill::new(self.pgid))
+	}
+
 	pub(super) fn signal_imp(&self, sig: Signal) -> Result<()> {
-		killpg(self.pgid, sig).map_err(Error::from)
+		killpg(self.pgid, sig).map_err(kill_error)
 	}
 
 	pub fn kill(&mut self) -> Result<()> {
-		self.signal_imp(Signal::SIGKILL)
+		self.kill_with(Signal::SIGKILL)
+	}
+
+	pub fn kill_with(&mut self, sig: Signal) -> Result<()> {
+		self.signal_imp(sig)
+	}
+
+	pub fn signal_leader(&self, sig: Signal) -> Result<()> {
+		// once reaped, the leader's pid may belong to an unrelated process
+		if self.leader_status().is_some() {
+			return Err(crate::Error::AlreadyExited.into());
+		}
+
+		// the pidfd can't reach another process, even if the leader was reaped behind our back
+		#[cfg(target_os = "linux")]
+		if let Some(fd) = self.pidfd() {
+			return pidfd_send_signal(fd, sig).map_err(kill_error);
+		}
+
+		kill(self.leader, sig).map_err(kill_error)
+	}
+
+	pub fn terminate(&mut self) -> Result<()> {
+		self.signal_imp(Signal::SIGTERM)
 	}
 
 	pub fn id(&self) -> u32 {
 		self.inner.id()
 	}
 
-	fn wait_imp(&mut self, flag: WaitPidFlag) -> Result<Option<ExitStatus>> {
-		let negpid = Pid::from_raw(-self.pgid.as_raw());
+	pub(super) fn process_count(&self) -> Result<usize> {
+		group_pids(self.pgid).map(|pids| pids.len())
+	}
+
+	pub(super) fn pids(&self) -> Result<Vec<u32>> {
+		let pids = group_pids(self.pgid)?;
+		Ok(pids.into_iter().map(|pid| pid.as_raw() as u32).collect())
+	}
+
+	#[cfg(target_os = "linux")]
+	pub(super) fn pidfd(&self) -> Option<BorrowedFd<'_>> {
+		self.pidfd.as_ref().map(AsFd::as_fd)
+	}
+
+	pub fn group_id(&self) -> u32 {
+		self.pgid.as_raw() as u32
+	}
+
+	/// Reaps processes in the group, returning whether there are none left to reap.
+	///
+	/// The status of every process reaped is recorded in `self.reaped`, and its resource usage
+	/// added to `self.rusage`.
+	fn wait_imp(&mut self, flag: WaitPidFlag) -> Result<bool> {
+		let target = wait_target(self.pgid, self.leader);
 
 		// Wait for processes in a loop until every process in this
 		// process group has exited (this ensures that we reap any
 		// zombies that may have been created if the parent exited after
 		// spawning children, but didn't wait for those children to
 		// exit).
-		let mut parent_exit_status: Option<ExitStatus> = None;
 		loop {
 			// we can't use the safe wrapper directly because it doesn't
 			// return the raw status, and we need it to convert to the
-			// std's ExitStatus.
+			// std's ExitStatus. wait4() is used to also get resource usage.
 			let mut status: i32 = 0;
+			let mut usage = mem::MaybeUninit::<libc::rusage>::zeroed();
 			match unsafe {
-				libc::waitpid(negpid.into(), &mut status as *mut libc::c_int, flag.bits())
+				libc::wait4(
+					target.into(),
+					&mut status as *mut libc::c_int,
+					flag.bits(),
+					usage.as_mut_ptr(),
+				)
 			} {
 				0 => {
 					// Zero should only happen if WNOHANG was passed in,
 					// and means that no processes have yet to exit.
-					return Ok(None);
+					return Ok(false);
 				}
 				-1 => {
 					match Errno::last() {
 						Errno::ECHILD => {
 							// No more children to reap; this is a
 							// graceful exit.
-							return Ok(parent_exit_status);
+							return Ok(true);
 						}
 						errno => {
 							return Err(Error::from(errno));
@@ -102,43 +259,211 @@ impl ChildImp {
 					}
 				}
 				pid => {
-					// *A* process exited. Was it the parent process
-					// that we started? If so, collect the exit signal,
-					// otherwise we reaped a zombie process and should
-					// continue in the loop.
-					if self.pgid.as_raw() == pid {
-						parent_exit_status = Some(ExitStatus::from_raw(status));
-					} else {
-						// Reaped a zombie child; keep looping.
-					}
+					// *A* process exited: either the parent process that
+					// we started, or a zombie. Either way, record its
+					// status and continue in the loop.
+					self.record_reaped(Pid::from_raw(pid), ExitStatus::from_raw(status));
+					// SAFETY: wait4() fills this in when it returns a pid
+					self.rusage.add(&unsafe { usage.assume_init() });
 				}
 			};
 		}
 	}
 
+	/// Records the status of a process reaped from the group.
+	///
+	/// The leader's PID can't be reused before the leader is reaped, so the first process reaped
+	/// with that PID is the leader. Any later one is an unrelated process which was given the same
+	/// PID, and is only recorded as a member of the group.
+	fn record_reaped(&mut self, pid: Pid, status: ExitStatus) {
+		if pid == self.leader && self.leader_exit.is_none() {
+			self.leader_exit = Some(status);
+		}
+		self.reaped.push((pid, status));
+	}
+
+	/// The leader's exit status, if it has been reaped.
+	fn leader_status(&self) -> Option<ExitStatus> {
+		self.leader_exit
+	}
+
+	/// Records the leader's exit status when it was collected through the inner [`Child`] instead.
+	fn record_leader(&mut self, status: ExitStatus) {
+		if self.leader_status().is_none() {
+			self.record_reaped(self.leader, status);
+		}
+		self.disarm_kill_on_drop();
+	}
+
 	pub fn wait(&mut self) -> Result<ExitStatus> {
 		if let Some(status) = self.try_wait()? {
 			return Ok(status);
 		}
 
-		match self.wait_imp(WaitPidFlag::empty()).transpose() {
-			None => self.inner.wait(),
-			Some(status) => status,
+		self.wait_imp(WaitPidFlag::empty())?;
+		match self.leader_status() {
+			Some(status) => {
+				self.disarm_kill_on_drop();
+				Ok(status)
+			}
+			None => {
+				let status = self.inner.wait()?;
+				self.record_leader(status);
+				Ok(status)
+			}
 		}
 	}
 
 	pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
-		match self.wait_imp(WaitPidFlag::WNOHANG) {
-			Ok(None) => self.inner.try_wait(),
-			otherwise => otherwise,
+		self.wait_imp(WaitPidFlag::WNOHANG)?;
+		match self.leader_status() {
+			Some(status) => {
+				self.disarm_kill_on_drop();
+				Ok(Some(status))
+			}
+			None => {
+				let status = self.inner.try_wait()?;
+				if let Some(status) = status {
+					self.record_leader(status);
+				}
+				Ok(status)
+			}
+		}
+	}
+
+	/// Reaps what has exited in the group, returning the leader's status once none of it is left.
+	pub(super) fn try_wait_group(&mut self) -> Result<Option<ExitStatus>> {
+		if !self.wait_imp(WaitPidFlag::WNOHANG)? {
+			return Ok(None);
+		}
+
+		match self.leader_status() {
+			Some(status) => {
+				self.disarm_kill_on_drop();
+				Ok(Some(status))
+			}
+			None => {
+				let status = self.inner.wait()?;
+				self.record_leader(status);
+				Ok(Some(status))
+			}
+		}
+	}
+
+	/// Waits for the leader to stop, be continued, or terminate.
+	///
+	/// Returns `None` once the leader has terminated, with its status recorded for `wait()`, which
+	/// should then be called to reap the rest of the group.
+	pub fn wait_stopped(&mut self) -> Result<Option<GroupStatus>> {
+		if self.leader_status().is_some() {
+			return Ok(None);
+		}
+
+		let flags = WaitPidFlag::WUNTRACED | WaitPidFlag::WCONTINUED;
+		let mut status: i32 = 0;
+		let mut usage = mem::MaybeUninit::<libc::rusage>::zeroed();
+		let res = loop {
+			// as in wait_imp(), the raw status is needed to build an ExitStatus
+			let res = unsafe {
+				libc::wait4(
+					self.leader.into(),
+					&mut status as *mut libc::c_int,
+					flags.bits(),
+					usage.as_mut_ptr(),
+				)
+			};
+			if res != -1 || Errno::last() != Errno::EINTR {
+				break res;
+			}
+		};
+
+		if res == -1 {
+			return match Errno::last() {
+				// the leader was already reaped through the inner Child
+				Errno::ECHILD => Ok(None),
+				errno => Err(Error::from(errno)),
+			};
+		}
+
+		if libc::WIFSTOPPED(status) {
+			let sig = Signal::try_from(libc::WSTOPSIG(status))?;
+			return Ok(Some(GroupStatus::Stopped(sig)));
+		}
+		if libc::WIFCONTINUED(status) {
+			return Ok(Some(GroupStatus::Continued));
+		}
+
+		self.record_reaped(self.leader, ExitStatus::from_raw(status));
+		// SAFETY: wait4() fills this in when it returns a pid
+		self.rusage.add(&unsafe { usage.assume_init() });
+		Ok(None)
+	}
+
+	pub fn wait_all(&mut self) -> Result<Vec<(u32, ExitStatus)>> {
+		// unlike wait(), don't return as soon as the leader has exited
+		self.wait_imp(WaitPidFlag::empty())?;
+		self.wait()?;
+
+		Ok(self
+			.reaped
+			.iter()
+			.map(|(pid, status)| (pid.as_raw() as u32, *status))
+			.collect())
+	}
+
+	pub fn wait_with_rusage(&mut self) -> Result<(ExitStatus, Rusage)> {
+		self.wait_all()?;
+		Ok((self.wait()?, self.rusage))
+	}
+
+	pub fn wait_timeout(&mut self, timeout: Duration) -> Result<Option<ExitStatus>> {
+		const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+		// There's no portable way to waitpid() with a timeout, so poll until the deadline.
+		let deadline = Instant::now() + timeout;
+
+		// On Linux, we can instead sleep until the leader exits, by polling its pidfd. Once that
+		// has woken us, fall back to the interval, in case the leader hasn't been reaped yet.
+		#[cfg(target_os = "linux")]
+		let mut leader_exited = false;
+
+		loop {
+			if let Some(status) = self.try_wait()? {
+				return Ok(Some(status));
+			}
+
+			let remaining = deadline.saturating_duration_since(Instant::now());
+			if remaining.is_zero() {
+				return Ok(None);
+			}
+
+			#[cfg(target_os = "linux")]
+			if let (false, Some(fd)) = (leader_exited, self.pidfd()) {
+				leader_exited = poll_exited(fd, remaining)?;
+				continue;
+			}
+
+			sleep(POLL_INTERVAL.min(remaining));
 		}
 	}
 
 	pub(super) fn read_both(
-		mut out_r: ChildStdout,
+		out_r: ChildStdout,
 		out_v: &mut Vec<u8>,
-		mut err_r: ChildStderr,
+		err_r: ChildStderr,
 		err_v: &mut Vec<u8>,
+	) -> Result<()> {
+		Self::stream_both(out_r, err_r, |source, data| match source {
+			OutputSource::Stdout => out_v.extend_from_slice(data),
+			OutputSource::Stderr => err_v.extend_from_slice(data),
+		})
+	}
+
+	/// Reads both streams to their end, passing what's read from either to `f` as it arrives.
+	pub(super) fn stream_both(
+		mut out_r: ChildStdout,
+		mut err_r: ChildStderr,
+		mut f: impl FnMut(OutputSource, &[u8]),
 	) -> Result<()> {
 		let out_fd = out_r.as_raw_fd();
 		let err_fd = err_r.as_raw_fd();
@@ -154,29 +479,43 @@ impl ChildImp {
 			PollFd::new(&err_bfd, PollFlags::POLLIN),
 		];
 
+		let mut buf = [0; 8192];
 		loop {
-			poll(&mut fds, -1)?;
+			// being interrupted by a signal is no reason to stop reading
+			match poll(&mut fds, -1) {
+				Err(Errno::EINTR) => continue,
+				res => res?,
+			};
 
-			if fds[0].revents().is_some() && read(&mut out_r, out_v)? {
+			if fds[0].revents().is_some()
+				&& read(&mut out_r, &mut buf, |data| f(OutputSource::Stdout, data))?
+			{
 				set_nonblocking(err_fd, false)?;
-				return err_r.read_to_end(err_v).map(drop);
+				return read_chunks(err_r, |data| f(OutputSource::Stderr, data));
 			}
-			if fds[1].revents().is_some() && read(&mut err_r, err_v)? {
+			if fds[1].revents().is_some()
+				&& read(&mut err_r, &mut buf, |data| f(OutputSource::Stderr, data))?
+			{
 				set_nonblocking(out_fd, false)?;
-				return out_r.read_to_end(out_v).map(drop);
+				return read_chunks(out_r, |data| f(OutputSource::Stdout, data));
 			}
 		}
 
-		fn read(r: &mut impl Read, dst: &mut Vec<u8>) -> Result<bool> {
-			match r.read_to_end(dst) {
-				Ok(_) => Ok(true),
-				Err(e) => {
-					if e.raw_os_error() == Some(libc::EWOULDBLOCK)
-						|| e.raw_os_error() == Some(libc::EAGAIN)
-					{
-						Ok(false)
-					} else {
-						Err(e)
+		/// Reads what's available, returning whether the end of the stream was reached.
+		fn read(r: &mut impl Read, buf: &mut [u8], mut f: impl FnMut(&[u8])) -> Result<bool> {
+			loop {
+				match r.read(buf) {
+					Ok(0) => return Ok(true),
+					Ok(n) => f(&buf[..n]),
+					Err(e) if e.kind() == ErrorKind::Interrupted => {}
+					Err(e) => {
+						return if e.raw_os_error() == Some(libc::EWOULDBLOCK)
+							|| e.raw_os_error() == Some(libc::EAGAIN)
+						{
+							Ok(false)
+						} else {
+							Err(e)
+						};
 					}
 				}
 			}
@@ -204,12 +543,33 @@ impl ChildImp {
 	}
 }
 
-pub trait UnixChildExt {
-	fn signal(&self, sig: Signal) -> Result<()>;
+/// What to waitpid() on to reap the group.
+fn wait_target(pgid: Pid, leader: Pid) -> Pid {
+	if pgid == leader {
+		Pid::from_raw(-pgid.as_raw())
+	} else {
+		// If the child joined an existing group, that group may contain other children of ours,
+		// which we have no business reaping: only wait on the child itself.
+		leader
+	}
 }
 
-impl UnixChildExt for ChildImp {
-	fn signal(&self, sig: Signal) -> Result<()> {
-		self.signal_imp(sig)
+/// Waits for a pidfd to become readable, which happens when its process exits.
+///
+/// Returns `false` if the timeout elapsed first, or if interrupted by a signal.
+#[cfg(target_os = "linux")]
+fn poll_exited(fd: BorrowedFd<'_>, timeout: Duration) -> Result<bool> {
+	// round up, so we don't spin when less than a millisecond is left
+	let millis = timeout
+		.as_micros()
+		.div_ceil(1000)
+		.try_into()
+		.unwrap_or(libc::c_int::MAX);
+
+	let mut fds = [PollFd::new(&fd, PollFlags::POLLIN)];
+	match poll(&mut fds, millis) {
+		Ok(n) => Ok(n > 0),
+		Err(Errno::EINTR) => Ok(false),
+		Err(err) => Err(Error::from(err)),
 	}
 }
@@ -1,37 +1,46 @@
 use std::{
-	io::{Read, Result},
-	mem,
+	convert::TryInto,
+	io::{Error, Read, Result},
+	os::windows::io::{AsHandle, AsRawHandle, BorrowedHandle, IntoRawHandle},
 	process::{Child, ChildStderr, ChildStdin, ChildStdout, ExitStatus},
+	sync::mpsc,
+	thread,
+	time::{Duration, Instant},
 };
-use winapi::{
-	shared::{
-		basetsd::ULONG_PTR,
-		minwindef::{DWORD, FALSE},
-	},
-	um::{
-		handleapi::CloseHandle, ioapiset::GetQueuedCompletionStatus, jobapi2::TerminateJobObject,
-		minwinbase::OVERLAPPED, winbase::INFINITE, winnt::HANDLE,
-	},
-};
+use winapi::um::winbase::INFINITE;
 
-use crate::winres::*;
+use super::read_chunks;
+use crate::{winres::*, ControlEvent, GroupKill, OutputSource, Signal};
 
 pub(super) struct ChildImp {
 	inner: Child,
 	handles: JobPort,
+	new_process_group: bool,
 }
 
 impl ChildImp {
-	pub fn new(inner: Child, job: HANDLE, completion_port: HANDLE) -> Self {
+	pub fn new(inner: Child, handles: JobPort, new_process_group: bool) -> Self {
 		Self {
 			inner,
-			handles: JobPort {
-				job,
-				completion_port,
-			},
+			handles,
+			new_process_group,
 		}
 	}
 
+	/// Assigns an already-spawned child to a new job object.
+	///
+	/// On failure, the child is given back along with the error.
+	pub(super) fn adopt(inner: Child) -> std::result::Result<Self, (Error, Child)> {
+		let handles = match job_object(false, &JobLimits::default()) {
+			Ok(handles) => handles,
+			Err(err) => return Err((err, inner)),
+		};
+		if let Err(err) = assign_child(inner.as_raw_handle(), handles.raw_job(), false) {
+			return Err((err, inner));
+		}
+		Ok(Self::new(inner, handles, false))
+	}
+
 	pub(super) fn take_stdin(&mut self) -> Option<ChildStdin> {
 		self.inner.stdin.take()
 	}
@@ -48,70 +57,198 @@ impl ChildImp {
 		&mut self.inner
 	}
 
+	pub(super) fn inner_ref(&self) -> &Child {
+		&self.inner
+	}
+
 	pub fn into_inner(self) -> Child {
-		// manually drop the completion port
-		let its = mem::ManuallyDrop::new(self.handles);
-		unsafe { CloseHandle(its.completion_port) };
+		let JobPort {
+			job,
+			completion_port,
+		} = self.handles;
+		drop(completion_port);
 		// we leave the job handle unclosed, otherwise the Child is useless
 		// (as closing it will terminate the job)
+		let _ = job.into_raw_handle();
 
 		// extract the Child
 		self.inner
 	}
 
-	pub fn kill(&mut self) -> Result<()> {
-		res_bool(unsafe { TerminateJobObject(self.handles.job, 1) })
+	pub fn detach(self) {
+		let JobPort {
+			job,
+			completion_port,
+		} = self.handles;
+		drop(completion_port);
+		// closing the job would terminate it if it's set to kill on close; if that can't be
+		// cleared, leave the handle unclosed, so that only happens when this process exits
+		if disarm_kill_on_close(job.as_raw_handle() as _).is_err() {
+			let _ = job.into_raw_handle();
+		}
 	}
 
-	pub fn id(&self) -> u32 {
-		self.inner.id()
+	pub fn kill(&mut self) -> Result<()> {
+		self.kill_with_code(1)
 	}
 
-	fn wait_imp(&self, timeout: DWORD) -> Result<()> {
-		let mut code: DWORD = 0;
-		let mut key: ULONG_PTR = 0;
-		let mut overlapped = mem::MaybeUninit::<OVERLAPPED>::uninit();
-		let mut lp_overlapped = overlapped.as_mut_ptr();
-
-		let result = unsafe {
-			GetQueuedCompletionStatus(
-				self.handles.completion_port,
-				&mut code,
-				&mut key,
-				&mut lp_overlapped,
-				timeout,
-			)
-		};
+	pub fn kill_with_code(&mut self, code: u32) -> Result<()> {
+		terminate_job(self.handles.raw_job(), code)
+	}
 
-		// ignore timing out errors unless the timeout was specified to INFINITE
-		// https://docs.microsoft.com/en-us/windows/win32/api/ioapiset/nf-ioapiset-getqueuedcompletionstatus
-		if timeout != INFINITE && result == FALSE && lp_overlapped.is_null() {
+	pub fn terminate(&mut self) -> Result<()> {
+		if self.interrupt().is_ok() {
 			return Ok(());
 		}
 
-		res_bool(result)?;
+		self.kill()
+	}
 
-		Ok(())
+	pub fn kill_with(&mut self, sig: Signal) -> Result<()> {
+		match sig {
+			Signal::Kill => self.kill(),
+			Signal::Terminate => self.terminate(),
+			Signal::Interrupt => self.interrupt(),
+		}
+	}
+
+	fn interrupt(&mut self) -> Result<()> {
+		self.send_control(ControlEvent::CtrlBreak)
+	}
+
+	pub fn send_control(&self, event: ControlEvent) -> Result<()> {
+		send_control(
+			self.handles.raw_job(),
+			Some(self.inner.id()),
+			self.new_process_group,
+			event,
+		)
+	}
+
+	pub(super) fn kill_handle(&self) -> Result<GroupKill> {
+		GroupKill::new(&self.handles.job)
+	}
+
+	pub(super) fn job_handle(&self) -> BorrowedHandle<'_> {
+		self.handles.job.as_handle()
+	}
+
+	pub(super) fn completion_port_handle(&self) -> BorrowedHandle<'_> {
+		self.handles.completion_port.as_handle()
+	}
+
+	pub(super) fn process_count(&self) -> Result<usize> {
+		job_active_processes(self.handles.raw_job()).map(|count| count as usize)
+	}
+
+	pub(super) fn pids(&self) -> Result<Vec<u32>> {
+		job_pids(self.handles.raw_job())
+	}
+
+	pub(super) fn job_accounting(&self) -> Result<crate::JobAccounting> {
+		job_accounting(self.handles.raw_job())
+	}
+
+	pub fn id(&self) -> u32 {
+		self.inner.id()
+	}
+
+	pub fn group_id(&self) -> u32 {
+		// there are no process groups, so use the leader as the closest equivalent
+		self.inner.id()
 	}
 
 	pub fn wait(&mut self) -> Result<ExitStatus> {
-		self.wait_imp(INFINITE)?;
+		wait_job_empty(&self.handles, INFINITE)?;
 		self.inner.wait()
 	}
 
 	pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
-		self.wait_imp(0)?;
+		wait_job_empty(&self.handles, 0)?;
+		self.inner.try_wait()
+	}
+
+	/// Returns the leader's status once no process is left in the job.
+	pub(super) fn try_wait_group(&mut self) -> Result<Option<ExitStatus>> {
+		if job_active_processes(self.handles.raw_job())? > 0 {
+			return Ok(None);
+		}
+
 		self.inner.try_wait()
 	}
 
+	pub fn wait_timeout(&mut self, timeout: Duration) -> Result<Option<ExitStatus>> {
+		let deadline = Instant::now() + timeout;
+		loop {
+			let remaining = deadline.saturating_duration_since(Instant::now());
+			let millis = remaining
+				.as_millis()
+				.try_into()
+				.unwrap_or(INFINITE - 1)
+				.min(INFINITE - 1);
+
+			if wait_job_empty(&self.handles, millis)? {
+				return self.inner.wait().map(Some);
+			}
+
+			if remaining.is_zero() {
+				return Ok(None);
+			}
+		}
+	}
+
 	pub(super) fn read_both(
 		mut out_r: ChildStdout,
 		out_v: &mut Vec<u8>,
 		mut err_r: ChildStderr,
 		err_v: &mut Vec<u8>,
 	) -> Result<()> {
-		out_r.read_to_end(out_v)?;
-		err_r.read_to_end(err_v)?;
+		// Read stderr on another thread, so that a child filling up one pipe while we're blocked
+		// reading the other can't deadlock.
+		let err_thread = thread::spawn(move || {
+			let mut err_buf = Vec::new();
+			err_r.read_to_end(&mut err_buf).map(|_| err_buf)
+		});
+
+		let out_res = out_r.read_to_end(out_v);
+		let err_res = err_thread
+			.join()
+			.unwrap_or_else(|_| Err(Error::other("stderr reader panicked")));
+
+		out_res?;
+		err_v.extend(err_res?);
 		Ok(())
 	}
+
+	/// Reads both streams to their end, passing what's read from either to `f` as it arrives.
+	pub(super) fn stream_both(
+		out_r: ChildStdout,
+		err_r: ChildStderr,
+		mut f: impl FnMut(OutputSource, &[u8]),
+	) -> Result<()> {
+		// Read each stream on its own thread, and hand what's read back to this one to call `f`.
+		let (tx, rx) = mpsc::channel();
+		thread::scope(|s| {
+			let err_tx = tx.clone();
+			// the receiver outlives both threads, so sending can't fail
+			let out_thread = s.spawn(move || {
+				read_chunks(out_r, |data| {
+					tx.send((OutputSource::Stdout, data.to_vec())).ok();
+				})
+			});
+			let err_thread = s.spawn(move || {
+				read_chunks(err_r, |data| {
+					err_tx.send((OutputSource::Stderr, data.to_vec())).ok();
+				})
+			});
+
+			for (source, data) in rx {
+				f(source, &data);
+			}
+
+			let panicked = |_| Err(Error::other("output reader panicked"));
+			out_thread.join().unwrap_or_else(panicked)?;
+			err_thread.join().unwrap_or_else(panicked)
+		})
+	}
 }
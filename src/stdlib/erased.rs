@@ -1,7 +1,6 @@
-use std::{
-	io::Result,
-	process::{Child, ExitStatus, Output},
-};
+use std::process::{Child, ExitStatus, Output};
+
+use crate::{Error, Result};
 
 use super::GroupChild;
 
@@ -9,6 +8,20 @@ use super::GroupChild;
 ///
 /// This is a helper which erases that a [`std::process::Child`] is a different type than a
 /// [`GroupChild`]. It forwards to the corresponding method on the inner type.
+///
+/// It can be created from either with `.into()`:
+///
+/// ```no_run
+/// use std::process::Command;
+/// use command_group::{CommandGroup, stdlib::ErasedChild};
+///
+/// let grouped = true;
+/// let child: ErasedChild = if grouped {
+///     Command::new("ls").group_spawn().expect("ls command didn't start").into()
+/// } else {
+///     Command::new("ls").spawn().expect("ls command didn't start").into()
+/// };
+/// ```
 #[derive(Debug)]
 pub enum ErasedChild {
 	/// A grouped process child.
@@ -19,11 +32,39 @@ pub enum ErasedChild {
 }
 
 impl ErasedChild {
+	/// Returns a mutable reference to the inner stdlib [`Child`].
+	///
+	/// For a grouped child, the same caveats as [`GroupChild::inner`] apply: the inner child may
+	/// not be in the same state as the wrapper, due to how methods like `wait` and `kill` are
+	/// implemented.
+	///
+	/// - Grouped: [`GroupChild::inner`]
+	/// - Ungrouped: the child itself
+	pub fn inner(&mut self) -> &mut Child {
+		match self {
+			Self::Grouped(c) => c.inner(),
+			Self::Ungrouped(c) => c,
+		}
+	}
+
+	/// Consumes itself and returns the inner stdlib [`Child`].
+	///
+	/// For a grouped child, the same caveats as [`GroupChild::into_inner`] apply.
+	///
+	/// - Grouped: [`GroupChild::into_inner`]
+	/// - Ungrouped: the child itself
+	pub fn into_inner(self) -> Child {
+		match self {
+			Self::Grouped(c) => c.into_inner(),
+			Self::Ungrouped(c) => c,
+		}
+	}
+
 	/// Returns the OS-assigned process (group) identifier.
 	///
 	/// - Grouped: [`GroupChild::id`]
 	/// - Ungrouped: [`Child::id`]
-	pub fn id(&mut self) -> u32 {
+	pub fn id(&self) -> u32 {
 		match self {
 			Self::Grouped(c) => c.id(),
 			Self::Ungrouped(c) => c.id(),
@@ -37,7 +78,18 @@ impl ErasedChild {
 	pub fn kill(&mut self) -> Result<()> {
 		match self {
 			Self::Grouped(c) => c.kill(),
-			Self::Ungrouped(c) => c.kill(),
+			Self::Ungrouped(c) => c.kill().map_err(Error::signal),
+		}
+	}
+
+	/// Attempts to force the child to exit, but does not wait for the request to take effect.
+	///
+	/// - Grouped: [`GroupChild::start_kill`]
+	/// - Ungrouped: [`Child::kill`], which doesn't wait either
+	pub fn start_kill(&mut self) -> Result<()> {
+		match self {
+			Self::Grouped(c) => c.start_kill(),
+			Self::Ungrouped(c) => c.kill().map_err(Error::signal),
 		}
 	}
 
@@ -48,7 +100,7 @@ impl ErasedChild {
 	pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
 		match self {
 			Self::Grouped(c) => c.try_wait(),
-			Self::Ungrouped(c) => c.try_wait(),
+			Self::Ungrouped(c) => c.try_wait().map_err(Error::wait),
 		}
 	}
 
@@ -59,7 +111,7 @@ impl ErasedChild {
 	pub fn wait(&mut self) -> Result<ExitStatus> {
 		match self {
 			Self::Grouped(c) => c.wait(),
-			Self::Ungrouped(c) => c.wait(),
+			Self::Ungrouped(c) => c.wait().map_err(Error::wait),
 		}
 	}
 
@@ -70,7 +122,7 @@ impl ErasedChild {
 	pub fn wait_with_output(self) -> Result<Output> {
 		match self {
 			Self::Grouped(c) => c.wait_with_output(),
-			Self::Ungrouped(c) => c.wait_with_output(),
+			Self::Ungrouped(c) => c.wait_with_output().map_err(Error::wait),
 		}
 	}
 
@@ -87,4 +139,39 @@ impl ErasedChild {
 			Self::Ungrouped(c) => c.signal(sig),
 		}
 	}
+
+	/// Sends a signal to the process, as far as Windows supports it.
+	///
+	/// Windows has no signals, so only [`Kill`](crate::Signal::Kill) and
+	/// [`Terminate`](crate::Signal::Terminate) are supported, and both forcibly terminate the
+	/// child. Other signals return an [`Unsupported`](std::io::ErrorKind::Unsupported) error.
+	///
+	/// - Grouped: [`GroupKill::kill`](crate::GroupKill::kill)
+	/// - Ungrouped: `TerminateProcess`
+	#[cfg(windows)]
+	pub fn signal(&self, sig: crate::Signal) -> Result<()> {
+		use std::os::windows::io::AsRawHandle;
+
+		match (self, sig) {
+			(Self::Grouped(c), crate::Signal::Kill | crate::Signal::Terminate) => {
+				c.kill_handle()?.kill()
+			}
+			(Self::Grouped(_), sig) => Err(Error::signal(crate::winres::unsupported_signal(sig))),
+			(Self::Ungrouped(c), sig) => {
+				crate::winres::signal_process(c.as_raw_handle(), sig).map_err(Error::signal)
+			}
+		}
+	}
+}
+
+impl From<GroupChild> for ErasedChild {
+	fn from(child: GroupChild) -> Self {
+		Self::Grouped(child)
+	}
+}
+
+impl From<Child> for ErasedChild {
+	fn from(child: Child) -> Self {
+		Self::Ungrouped(child)
+	}
 }
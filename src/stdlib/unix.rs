@@ -1,6 +1,11 @@
 use std::{os::unix::process::CommandExt, process::Command};
 
-use crate::{builder::CommandGroupBuilder, GroupChild};
+use nix::unistd::getpgrp;
+
+use crate::{
+	builder::{ChildSetup, CommandGroupBuilder},
+	GroupChild,
+};
 
 impl CommandGroupBuilder<'_, Command> {
 	/// Executes the command as a child process group, returning a handle to it.
@@ -22,7 +27,39 @@ impl CommandGroupBuilder<'_, Command> {
 	///         .spawn()
 	///         .expect("ls command failed to start");
 	/// ```
-	pub fn spawn(&mut self) -> std::io::Result<GroupChild> {
-		self.command.process_group(0).spawn().map(GroupChild::new)
+	pub fn spawn(&mut self) -> crate::Result<GroupChild> {
+		self.spawn_imp().map_err(crate::Error::spawn)
+	}
+
+	fn spawn_imp(&mut self) -> std::io::Result<GroupChild> {
+		let process_group = if self.new_session {
+			// The group an earlier spawn set on the command can't be unset, and the child would
+			// then lead it before calling setsid(), which would fail. Joining our own group
+			// instead leaves the child where it starts out.
+			if self.sets_process_group {
+				self.command.process_group(getpgrp().as_raw());
+			}
+
+			// setsid() also creates a new process group, led by the child
+			0
+		} else {
+			self.command.process_group(self.process_group);
+			self.sets_process_group = true;
+			self.process_group
+		};
+
+		// the command joins the group itself, so the hook is only needed for the other options
+		let setup = self.child_setup(None)?;
+		if setup.is_some() && self.register_setup_hook() {
+			unsafe {
+				self.command.pre_exec(ChildSetup::run_current);
+			}
+		}
+
+		let kill_on_drop = self.kill_on_drop.then_some(self.kill_on_drop_signal);
+		let _current = setup.as_ref().map(ChildSetup::make_current);
+		self.command
+			.spawn()
+			.map(|child| GroupChild::new(child, process_group, kill_on_drop))
 	}
 }
@@ -1,17 +1,16 @@
 //! Implementation of process group extensions for [Tokio](https://tokio.rs)’s
 //! asynchronous [`Command` type](::tokio::process::Command).
 
-use std::{
-	io::Result,
-	process::{ExitStatus, Output},
-};
+use std::process::{ExitStatus, Output};
 
 use tokio::process::Command;
 
-use crate::{builder::CommandGroupBuilder, AsyncGroupChild};
+use crate::{builder::CommandGroupBuilder, AsyncGroupChild, Error, Result};
 
 #[doc(inline)]
 pub use erased::ErasedChild;
+#[doc(inline)]
+pub use set::GroupSet;
 
 #[cfg(target_family = "windows")]
 mod windows;
@@ -21,11 +20,14 @@ mod unix;
 
 pub(crate) mod child;
 pub(crate) mod erased;
+mod set;
 
 /// Extensions for [`Command`](::tokio::process::Command) adding support for process groups.
 ///
-/// This uses [`async_trait`] for now to provide async methods as a trait.
-#[async_trait::async_trait]
+/// The async methods are declared as plain `async fn`s. Their futures are `Send` when called on
+/// Tokio's `Command` directly, but that can't be relied upon in code generic over this trait.
+// the only implementor is Command, so the Send-ness of the futures is always known to callers
+#[allow(async_fn_in_trait)]
 pub trait AsyncCommandGroup {
 	/// Executes the command as a child process group, returning a handle to it.
 	///
@@ -52,9 +54,29 @@ pub trait AsyncCommandGroup {
 		self.group().spawn()
 	}
 
-	/// Converts the implementor into a [`CommandGroupBuilder`](crate::CommandGroupBuilder), which can be used to
-	/// set flags that are not available on the `Command` type.
-	fn group(&mut self) -> crate::builder::CommandGroupBuilder<tokio::process::Command>;
+	/// Converts the implementor into a [`CommandGroupBuilder`], which can be used to set flags
+	/// that are not available on the `Command` type.
+	fn group(&mut self) -> crate::builder::CommandGroupBuilder<'_, tokio::process::Command>;
+
+	/// Converts the implementor into a [`CommandGroupBuilder`] which owns it, so that it can be
+	/// stored and spawned from later.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let mut builder = Command::new("ls").into_group();
+	/// builder.kill_on_drop(true);
+	/// builder.spawn().expect("ls command failed to start");
+	/// # }
+	/// ```
+	fn into_group(self) -> CommandGroupBuilder<'static, tokio::process::Command>
+	where
+		Self: Sized;
 
 	/// Executes the command as a child process group, waiting for it to finish and
 	/// collecting all of its output.
@@ -123,11 +145,50 @@ pub trait AsyncCommandGroup {
 		let mut child = self.group_spawn()?;
 		child.wait().await
 	}
+
+	/// Executes the command either as a child process group or as a plain child, returning an
+	/// [`ErasedChild`] handle to it.
+	///
+	/// This lets the choice of grouping the command be made at runtime, without having to
+	/// branch and wrap the child by hand.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let grouped = std::env::var_os("NO_GROUP").is_none();
+	/// let mut child = Command::new("ls")
+	///         .spawn_erased(grouped)
+	///         .expect("ls command failed to start");
+	/// child.wait().await.expect("ls command wasn't running");
+	/// # }
+	/// ```
+	fn spawn_erased(&mut self, grouped: bool) -> Result<ErasedChild> {
+		let mut builder = self.group();
+		if grouped {
+			builder.spawn().map(ErasedChild::from)
+		} else {
+			builder
+				.command
+				.spawn()
+				.map(ErasedChild::from)
+				.map_err(Error::spawn)
+		}
+	}
 }
 
-#[async_trait::async_trait]
 impl AsyncCommandGroup for Command {
 	fn group<'a>(&'a mut self) -> CommandGroupBuilder<'a, Command> {
 		CommandGroupBuilder::new(self)
 	}
+
+	fn into_group(self) -> CommandGroupBuilder<'static, Command> {
+		CommandGroupBuilder::owned(self)
+	}
 }
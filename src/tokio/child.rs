@@ -1,21 +1,38 @@
 use std::{
 	fmt,
-	io::Result,
+	future::{Future, IntoFuture},
+	io,
+	pin::Pin,
 	process::{ExitStatus, Output},
+	time::Duration,
 };
 
-use tokio::{io::AsyncReadExt, process::Child};
+use tokio::{
+	io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader},
+	process::Child,
+};
+use tokio_stream::{
+	wrappers::{LinesStream, SplitStream},
+	Stream, StreamExt,
+};
+
+use crate::{
+	error::{ignore_broken_pipe, ignore_exited},
+	Error, GroupKill, LimitedOutput, OutputLine, OutputSource, Result, TimedOutput,
+};
 
 #[cfg(unix)]
-pub(self) use unix::ChildImp;
+use unix::ChildImp;
 #[cfg(windows)]
-pub(self) use windows::ChildImp;
+use windows::ChildImp;
 
 #[cfg(unix)]
 use nix::sys::signal::Signal;
 
 #[cfg(windows)]
-use winapi::um::winnt::HANDLE;
+use crate::winres::JobPort;
+#[cfg(windows)]
+use std::os::windows::io::BorrowedHandle;
 
 #[cfg(unix)]
 mod unix;
@@ -53,23 +70,32 @@ pub struct AsyncGroupChild {
 
 impl fmt::Debug for AsyncGroupChild {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		f.debug_struct("AsyncGroupChild").finish()
+		// only what's already known, as this shouldn't block or reap anything
+		let inner = self.imp.inner_ref();
+		f.debug_struct("AsyncGroupChild")
+			.field("id", &self.id())
+			.field("group_id", &self.group_id())
+			.field("exit_status", &self.exitstatus)
+			.field("stdin", &inner.stdin.is_some())
+			.field("stdout", &inner.stdout.is_some())
+			.field("stderr", &inner.stderr.is_some())
+			.finish()
 	}
 }
 
 impl AsyncGroupChild {
 	#[cfg(unix)]
-	pub(crate) fn new(inner: Child) -> Self {
+	pub(crate) fn new(inner: Child, process_group: i32, kill_on_drop: Option<Signal>) -> Self {
 		Self {
-			imp: ChildImp::new(inner),
+			imp: ChildImp::new(inner, process_group, kill_on_drop),
 			exitstatus: None,
 		}
 	}
 
 	#[cfg(windows)]
-	pub(crate) fn new(inner: Child, j: HANDLE, c: HANDLE) -> Self {
+	pub(crate) fn new(inner: Child, handles: JobPort, new_process_group: bool) -> Self {
 		Self {
-			imp: ChildImp::new(inner, j, c),
+			imp: ChildImp::new(inner, handles, new_process_group),
 			exitstatus: None,
 		}
 	}
@@ -109,6 +135,9 @@ impl AsyncGroupChild {
 	/// methods like `wait` and `kill` are implemented. It is not recommended to use this method
 	/// _after_ using any of the other methods on this struct.
 	///
+	/// If the group was spawned with `kill_on_drop`, this disarms it on Unix: the returned child is
+	/// not killed when dropped.
+	///
 	#[cfg_attr(
 		windows,
 		doc = "On Windows, this unnavoidably leaves a handle unclosed. Prefer [`inner()`](Self::inner)."
@@ -135,9 +164,43 @@ impl AsyncGroupChild {
 		self.imp.into_inner()
 	}
 
+	/// Lets the group carry on running on its own, and drops this handle to it.
+	///
+	/// This disarms `kill_on_drop`, so the group is neither signalled nor killed, now or when
+	/// this process exits. On Windows, the job object is told not to kill its processes when it's
+	/// closed, and its handles are then closed. The group's stdio handles are closed too, so
+	/// redirect them to files or [`Stdio::null()`](std::process::Stdio::null) if it should keep
+	/// writing output.
+	///
+	/// Unlike [`into_inner()`](Self::into_inner), which hands the leader over to be managed
+	/// through the standard library, this is for when nothing more is to be done with the group,
+	/// such as to start a daemon. On Unix, the group is no longer waited on, so its processes are
+	/// left as zombies once they exit, until this process exits too.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use std::process::Stdio;
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// Command::new("my-daemon")
+	///         .stdout(Stdio::null())
+	///         .group()
+	///         .kill_on_drop(true)
+	///         .spawn()
+	///         .expect("my-daemon command didn't start")
+	///         .detach();
+	/// ```
+	pub fn detach(self) {
+		self.imp.detach();
+	}
+
 	/// Forces the child process group to exit.
 	///
-	/// If the group has already exited, an [`InvalidInput`] error is returned.
+	/// If the group no longer exists, [`Error::AlreadyExited`] is returned. On Unix, that's once
+	/// every process in it has exited and been reaped; on Windows, once every process in the job
+	/// object has exited.
 	///
 	/// This is equivalent to sending a SIGKILL on Unix platforms.
 	///
@@ -161,8 +224,6 @@ impl AsyncGroupChild {
 	/// }
 	/// # }
 	/// ```
-	///
-	/// [`InvalidInput`]: std::io::ErrorKind::InvalidInput
 	pub async fn kill(&mut self) -> Result<()> {
 		self.start_kill()?;
 		self.wait().await?;
@@ -179,15 +240,306 @@ impl AsyncGroupChild {
 	///
 	/// See [the Tokio documentation](Child::start_kill) for more.
 	///
-	/// [`InvalidInput`]: std::io::ErrorKind::InvalidInput
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let mut command = Command::new("yes");
+	/// if let Ok(mut child) = command.group_spawn() {
+	///     child.start_kill().expect("command wasn't running");
+	///     child.wait().await.expect("command didn't exit");
+	/// } else {
+	///     println!("yes command didn't start");
+	/// }
+	/// # }
+	/// ```
 	pub fn start_kill(&mut self) -> Result<()> {
-		self.imp.start_kill()
+		self.imp.start_kill().map_err(Error::signal)
+	}
+
+	/// Attempts to force the child to exit with the given exit code, but does not wait for the
+	/// request to take effect.
+	///
+	/// This is the same as [`start_kill()`](Self::start_kill), which uses exit code 1, except that
+	/// every process in the job object exits with `code`. This can be used to tell a forced
+	/// termination apart from a process exiting with 1 on its own.
+	///
+	/// Waiting on the child afterwards reports `code` as the leader's exit code: its status is
+	/// only read once its process has been terminated, at which point the code is final. If the
+	/// leader had already exited on its own by the time of the kill, its own exit code is reported
+	/// instead.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let mut child = Command::new("cmd").arg("/C").arg("pause").group_spawn().unwrap();
+	/// child.start_kill_with_code(137).expect("command wasn't running");
+	/// let status = child.wait().await.expect("command didn't exit");
+	/// assert_eq!(status.code(), Some(137));
+	/// # }
+	/// ```
+	#[cfg(windows)]
+	pub fn start_kill_with_code(&mut self, code: u32) -> Result<()> {
+		self.imp.start_kill_with_code(code).map_err(Error::signal)
+	}
+
+	/// Asks the child process group to exit, but does not wait for it to do so.
+	///
+	/// On Unix, this sends SIGTERM to every process in the group.
+	///
+	/// On Windows, if the group was created with the `CREATE_NEW_PROCESS_GROUP` creation flag,
+	/// this sends a `CTRL_BREAK_EVENT` to the group's console. Otherwise, or if that fails, it
+	/// falls back to terminating the job object, like [`start_kill()`](Self::start_kill).
+	///
+	/// Unlike `kill`, processes may handle or ignore this request. Use [`wait()`](Self::wait) or
+	/// [`try_wait()`](Self::try_wait) to find out whether the group actually exited.
+	///
+	/// If the group no longer exists, this returns [`Error::AlreadyExited`], like `kill` does.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let mut command = Command::new("yes");
+	/// if let Ok(mut child) = command.group_spawn() {
+	///     child.terminate().expect("command wasn't running");
+	///     child.wait().await.expect("command didn't exit");
+	/// } else {
+	///     println!("yes command didn't start");
+	/// }
+	/// # }
+	/// ```
+	pub fn terminate(&mut self) -> Result<()> {
+		self.imp.terminate().map_err(Error::signal)
+	}
+
+	/// Sends a signal to the group leader only, rather than to the whole group.
+	///
+	/// [`UnixChildExt::signal()`](crate::UnixChildExt::signal) sends signals to every process in
+	/// the group. This instead only signals the process which was spawned, for example to ask a
+	/// supervisor to reload its configuration without disturbing its workers, or to let a shell
+	/// handle SIGWINCH itself.
+	///
+	/// Returns [`Error::AlreadyExited`] if the leader has already been waited on, as its process
+	/// ID may since have been reused.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use tokio::process::Command;
+	/// use command_group::{AsyncCommandGroup, Signal};
+	///
+	/// let mut command = Command::new("bash");
+	/// if let Ok(mut child) = command.group_spawn() {
+	///     child.signal_leader(Signal::SIGWINCH).expect("command wasn't running");
+	/// } else {
+	///     println!("bash command didn't start");
+	/// }
+	/// # }
+	/// ```
+	#[cfg(unix)]
+	pub fn signal_leader(&mut self, sig: Signal) -> Result<()> {
+		self.imp.signal_leader(sig).map_err(Error::signal)
+	}
+
+	/// Sends a console control event to the group, like pressing Ctrl-C or Ctrl-Break in its
+	/// console.
+	///
+	/// This lets console programs exit gracefully, which [`kill()`](Self::kill) doesn't allow. It
+	/// does not wait for the group to exit.
+	///
+	/// This requires the group to have been spawned with
+	/// [`new_process_group`](crate::builder::CommandGroupBuilder::new_process_group), or the
+	/// `CREATE_NEW_PROCESS_GROUP` creation flag, and fails with [`Unsupported`] otherwise. Once
+	/// every process in the group has exited, this fails with [`Error::AlreadyExited`]. See
+	/// [`ControlEvent`](crate::ControlEvent) for caveats with Ctrl-C.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use tokio::process::Command;
+	/// use command_group::{AsyncCommandGroup, ControlEvent};
+	///
+	/// let mut child = Command::new("ping")
+	///     .args(["-t", "localhost"])
+	///     .group()
+	///     .new_process_group(true)
+	///     .spawn()
+	///     .expect("ping command didn't start");
+	/// child.send_control(ControlEvent::CtrlBreak).expect("command wasn't running");
+	/// child.wait().await.expect("command didn't exit");
+	/// # }
+	/// ```
+	///
+	/// [`Unsupported`]: std::io::ErrorKind::Unsupported
+	#[cfg(windows)]
+	pub fn send_control(&self, event: crate::ControlEvent) -> Result<()> {
+		self.imp.send_control(event).map_err(Error::signal)
+	}
+
+	/// Sends a Ctrl-Break event to the group.
+	///
+	/// This is the same as [`send_control()`](Self::send_control) with
+	/// [`ControlEvent::CtrlBreak`](crate::ControlEvent::CtrlBreak). Most console programs exit on
+	/// Ctrl-Break, and it's the only control event which can reliably be sent to the group alone.
+	#[cfg(windows)]
+	pub fn send_ctrl_break(&self) -> Result<()> {
+		self.send_control(crate::ControlEvent::CtrlBreak)
+	}
+
+	/// Sends a Ctrl-C event to the group.
+	///
+	/// This is the same as [`send_control()`](Self::send_control) with
+	/// [`ControlEvent::CtrlC`](crate::ControlEvent::CtrlC). Windows can't limit Ctrl-C to a single
+	/// process group, and processes in a new process group ignore it by default, so this is
+	/// unreliable; prefer [`send_ctrl_break()`](Self::send_ctrl_break).
+	#[cfg(windows)]
+	pub fn send_ctrl_c(&self) -> Result<()> {
+		self.send_control(crate::ControlEvent::CtrlC)
+	}
+
+	/// Returns a handle which can kill the group from another task or thread.
+	///
+	/// The handle doesn't borrow this child, and can be cloned and sent across threads. See
+	/// [`GroupKill`] for caveats, notably around process group ID reuse on Unix.
+	///
+	/// On Windows, this duplicates the job object handle, which may fail.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use std::time::Duration;
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let mut child = Command::new("yes").group_spawn().expect("yes command didn't start");
+	/// let killer = child.kill_handle().expect("failed to get a kill handle");
+	/// tokio::spawn(async move {
+	///     tokio::time::sleep(Duration::from_secs(1)).await;
+	///     killer.kill().expect("command wasn't running");
+	/// });
+	/// child.wait().await.expect("command wasn't running");
+	/// # }
+	/// ```
+	pub fn kill_handle(&self) -> Result<GroupKill> {
+		self.imp.kill_handle().map_err(Error::from)
+	}
+
+	/// Borrows the handle to the job object the group runs in.
+	///
+	/// This can be used to query or configure the job object directly, for example with
+	/// `QueryInformationJobObject` or `SetInformationJobObject` on its raw handle, to apply limits
+	/// this crate doesn't provide.
+	///
+	/// The handle is still owned by this child, and the borrow ensures it isn't used after the
+	/// child has been dropped. Do not close its raw handle: that would also terminate the group if
+	/// it was spawned with `kill_on_drop`. To keep a handle to the job around for longer, use
+	/// [`BorrowedHandle::try_clone_to_owned()`], which duplicates it. After
+	/// [`into_inner()`](Self::into_inner), the job handle is deliberately left open, and is
+	/// never closed.
+	///
+	/// The group is already running by the time this is available, so limits configured through
+	/// this race with the processes in it: anything they did or started before the limits were set
+	/// isn't affected. Prefer the builder's options for limits that must apply from the start.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let child = Command::new("cmd").arg("/C").arg("pause").group_spawn().unwrap();
+	/// let job = child.job_handle();
+	/// // e.g. call QueryInformationJobObject(job.as_raw_handle(), ...) here
+	/// # }
+	/// ```
+	#[cfg(windows)]
+	pub fn job_handle(&self) -> BorrowedHandle<'_> {
+		self.imp.job_handle()
+	}
+
+	/// Borrows the handle to the I/O completion port which receives the job object's
+	/// notifications.
+	///
+	/// The same ownership rules apply as for [`job_handle()`](Self::job_handle), except that the
+	/// completion port is closed by `into_inner()`. This child reads from the completion port to
+	/// wait for the group to exit: dequeuing packets from it yourself will break waiting.
+	#[cfg(windows)]
+	pub fn completion_port_handle(&self) -> BorrowedHandle<'_> {
+		self.imp.completion_port_handle()
+	}
+
+	/// Returns accounting information for the job object: CPU times, peak memory usage, and the
+	/// number of processes in the group.
+	///
+	/// This can be called at any time, including while the group is running and after it exited.
+	/// See [`JobAccounting`](crate::JobAccounting) for details.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let mut child = Command::new("cmd").arg("/C").arg("dir").group_spawn().unwrap();
+	/// child.wait().await.expect("command wasn't running");
+	/// let accounting = child.job_accounting().expect("failed to query job");
+	/// println!("used {:?} of CPU time", accounting.user_time + accounting.kernel_time);
+	/// # }
+	/// ```
+	#[cfg(windows)]
+	pub fn job_accounting(&self) -> Result<crate::JobAccounting> {
+		self.imp.job_accounting().map_err(Error::from)
 	}
 
-	/// Returns the OS-assigned process group identifier.
+	/// Returns the OS-assigned process identifier of the child, which is the group leader.
 	///
 	/// Like Tokio, this returns `None` if the child process group has alread exited, to avoid
-	/// holding onto an expired (and possibly reused) PGID.
+	/// holding onto an expired (and possibly reused) PID.
+	///
+	/// This is the same as [`leader_id()`](Self::leader_id). On Unix, this is usually also the
+	/// process group ID, as the child leads a new group. That isn't the case if it joined an
+	/// existing group with the builder's `process_group()`: use [`group_id()`](Self::group_id) to
+	/// get the group ID itself.
 	///
 	/// See [the Tokio documentation](Child::id) for more.
 	///
@@ -203,8 +555,8 @@ impl AsyncGroupChild {
 	///
 	/// let mut command = Command::new("ls");
 	/// if let Ok(child) = command.group_spawn() {
-	///     if let Some(pgid) = child.id() {
-	///         println!("Child group's ID is {}", pgid);
+	///     if let Some(pid) = child.id() {
+	///         println!("Child's ID is {}", pid);
 	///     } else {
 	///         println!("Child group is gone");
 	///     }
@@ -217,20 +569,13 @@ impl AsyncGroupChild {
 		self.imp.id()
 	}
 
-	/// Waits for the child group to exit completely, returning the status that the process leader
-	/// exited with.
-	///
-	/// See [the Tokio documentation](Child::wait) for more.
+	/// Returns the process group ID which signals are sent to.
 	///
-	/// The current implementation spawns a blocking task on the Tokio thread pool; contributions
-	/// are welcome for a better version.
+	/// This is the same as [`id()`](Self::id), unless the child joined an existing group with the
+	/// builder's `process_group()`. Like `id()`, this returns `None` once the child has exited.
 	///
-	/// An important consideration on Unix platforms is that there is no way to cancel the `wait`
-	/// syscall. _Cancelling this future_ will **not** cancel that underlying `wait` call. That has
-	/// consequences: a `wait`ed process that exits will have its resources cleaned up by the kernel.
-	/// If the application is no longer listening for that `wait` returning, it will not know that
-	/// the process has been cleaned up, and will try to wait on it again. That in turn may fail, or
-	/// could even attach to a recycled PID which would then point to a completely different process.
+	/// On Windows, there are no process group IDs: the group is a job object, which has no ID.
+	/// This returns the leader's process ID instead, like `id()`.
 	///
 	/// # Examples
 	///
@@ -243,28 +588,25 @@ impl AsyncGroupChild {
 	/// use command_group::AsyncCommandGroup;
 	///
 	/// let mut command = Command::new("ls");
-	/// if let Ok(mut child) = command.group_spawn() {
-	///     child.wait().await.expect("command wasn't running");
-	///     println!("Child has finished its execution!");
+	/// if let Ok(child) = command.group_spawn() {
+	///     if let Some(pgid) = child.group_id() {
+	///         println!("Child group's ID is {}", pgid);
+	///     } else {
+	///         println!("Child group is gone");
+	///     }
 	/// } else {
 	///     println!("ls command didn't start");
 	/// }
 	/// # }
 	/// ```
-	pub async fn wait(&mut self) -> Result<ExitStatus> {
-		if let Some(es) = self.exitstatus {
-			return Ok(es);
-		}
-
-		drop(self.imp.take_stdin());
-		let status = self.imp.wait().await?;
-		self.exitstatus = Some(status);
-		Ok(status)
+	pub fn group_id(&self) -> Option<u32> {
+		self.imp.group_id()
 	}
 
-	/// Attempts to collect the exit status of the child if it has already exited.
+	/// Returns the process ID of the group leader, the process which was spawned.
 	///
-	/// See [the Tokio documentation](Child::try_wait) for more.
+	/// This is the same as [`id()`](Self::id), and exists to make it explicit which of the leader
+	/// and the group is meant, next to [`group_id()`](Self::group_id).
 	///
 	/// # Examples
 	///
@@ -276,65 +618,410 @@ impl AsyncGroupChild {
 	/// use tokio::process::Command;
 	/// use command_group::AsyncCommandGroup;
 	///
-	/// let mut child = Command::new("ls").group_spawn().unwrap();
-	///
-	/// match child.try_wait() {
-	///     Ok(Some(status)) => println!("exited with: {}", status),
-	///     Ok(None) => {
-	///         println!("status not ready yet, let's really wait");
-	///         let res = child.wait().await;
-	///         println!("result: {:?}", res);
+	/// let mut command = Command::new("ls");
+	/// if let Ok(child) = command.group_spawn() {
+	///     if let Some(pid) = child.leader_id() {
+	///         println!("Child group's leader is {}", pid);
+	///     } else {
+	///         println!("Child group is gone");
 	///     }
-	///     Err(e) => println!("error attempting to wait: {}", e),
+	/// } else {
+	///     println!("ls command didn't start");
 	/// }
 	/// # }
 	/// ```
-	pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
-		if self.exitstatus.is_some() {
-			return Ok(self.exitstatus);
-		}
-
-		match self.imp.try_wait()? {
-			Some(es) => {
-				self.exitstatus = Some(es);
-				Ok(Some(es))
-			}
-			None => Ok(None),
-		}
+	pub fn leader_id(&self) -> Option<u32> {
+		self.imp.id()
 	}
 
-	/// Simultaneously waits for the child to exit and collect all remaining output on the
-	/// stdout/stderr handles, returning an `Output` instance.
+	/// Returns how many processes are currently in the group.
 	///
-	/// See [the Tokio documentation](Child::wait_with_output) for more.
+	/// This includes the leader while it's running, and is 0 once every process in the group has
+	/// exited. For example, this can be used to wait until a daemon's workers have all exited.
+	///
+	/// On Windows, this is the number of active processes in the job object.
+	///
+	/// On Unix, this counts the processes whose process group ID is the group's. If the child
+	/// joined an existing group with the builder's `process_group()`, that includes processes
+	/// which were already in it. Once the group is gone, its ID may be reused by an unrelated
+	/// group, which would then be counted. On Linux, this reads `/proc`, and doesn't count
+	/// zombies. On macOS, zombies are counted. On other platforms, this returns an
+	/// [`Unsupported`](std::io::ErrorKind::Unsupported) error.
 	///
 	/// # Examples
 	///
 	/// Basic usage:
 	///
-	/// ```should_panic
+	/// ```no_run
 	/// # #[tokio::main]
 	/// # async fn main() {
-	/// use std::process::Stdio;
 	/// use tokio::process::Command;
 	/// use command_group::AsyncCommandGroup;
 	///
-	/// let child = Command::new("/bin/cat")
-	///     .arg("file.txt")
-	///     .stdout(Stdio::piped())
-	///     .group_spawn()
-	///     .expect("failed to execute child");
-	///
-	/// let output = child
-	///     .wait_with_output()
-	///     .await
-	///     .expect("failed to wait on child");
-	///
-	/// assert!(output.status.success());
+	/// let child = Command::new("sh").arg("-c").arg("sleep 10 & sleep 10 &").group_spawn().unwrap();
+	/// println!("{} processes running", child.process_count().expect("failed to count"));
 	/// # }
 	/// ```
-	pub async fn wait_with_output(mut self) -> Result<Output> {
-		drop(self.imp.take_stdin());
+	pub fn process_count(&self) -> Result<usize> {
+		self.imp.process_count().map_err(Error::from)
+	}
+
+	/// Returns the process IDs of the processes currently in the group, in ascending order.
+	///
+	/// This includes the leader while it's running, and can help find out which lingering member
+	/// is keeping a group from exiting. It is a snapshot: processes may have exited or been started
+	/// by the time it's returned. The same platform differences as for
+	/// [`process_count()`](Self::process_count) apply: on Windows, this lists the processes in the
+	/// job object; on Unix, those with the group's process group ID, which is only supported on
+	/// Linux and macOS.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let child = Command::new("sh").arg("-c").arg("sleep 10 & sleep 10 &").group_spawn().unwrap();
+	/// for pid in child.pids().expect("failed to list processes") {
+	///     println!("{pid}");
+	/// }
+	/// # }
+	/// ```
+	pub fn pids(&self) -> Result<Vec<u32>> {
+		let mut pids = self.imp.pids()?;
+		pids.sort_unstable();
+		Ok(pids)
+	}
+
+	/// Waits for the child group to exit completely, returning the status that the process leader
+	/// exited with.
+	///
+	/// See [the Tokio documentation](Child::wait) for more.
+	///
+	/// Once the leader has exited, the rest of the group is polled for at an increasing interval,
+	/// up to 100ms, until every process has exited.
+	///
+	/// # Cancel safety
+	///
+	/// This method is cancel safe, so it can be used with a timeout or in `tokio::select!`. If the
+	/// future is dropped before it completes, no work is left running in the background. If the
+	/// leader had already exited by then, its status is kept, and will be returned by the next
+	/// call to `wait()`, which also resumes reaping the rest of the group.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let mut command = Command::new("ls");
+	/// if let Ok(mut child) = command.group_spawn() {
+	///     child.wait().await.expect("command wasn't running");
+	///     println!("Child has finished its execution!");
+	/// } else {
+	///     println!("ls command didn't start");
+	/// }
+	/// # }
+	/// ```
+	pub async fn wait(&mut self) -> Result<ExitStatus> {
+		if let Some(es) = self.exitstatus {
+			return Ok(es);
+		}
+
+		drop(self.imp.take_stdin());
+		let status = self.imp.wait().await.map_err(Error::wait)?;
+		self.exitstatus = Some(status);
+		Ok(status)
+	}
+
+	/// Attempts to collect the exit status of the child if it has already exited.
+	///
+	/// See [the Tokio documentation](Child::try_wait) for more.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let mut child = Command::new("ls").group_spawn().unwrap();
+	///
+	/// match child.try_wait() {
+	///     Ok(Some(status)) => println!("exited with: {}", status),
+	///     Ok(None) => {
+	///         println!("status not ready yet, let's really wait");
+	///         let res = child.wait().await;
+	///         println!("result: {:?}", res);
+	///     }
+	///     Err(e) => println!("error attempting to wait: {}", e),
+	/// }
+	/// # }
+	/// ```
+	pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		if self.exitstatus.is_some() {
+			return Ok(self.exitstatus);
+		}
+
+		match self.imp.try_wait().map_err(Error::wait)? {
+			Some(es) => {
+				self.exitstatus = Some(es);
+				Ok(Some(es))
+			}
+			None => Ok(None),
+		}
+	}
+
+	/// Waits for the child group to exit completely by blocking the current thread, returning the
+	/// status that the process leader exited with.
+	///
+	/// This is for synchronous code which holds an async child, such as a `Drop` implementation
+	/// or a teardown path at shutdown. It doesn't go through Tokio, so no runtime is needed. On
+	/// Unix, the group is reaped with blocking `waitpid()` calls; on Windows, this waits on the job
+	/// object's completion port until no process is left in it. Like [`wait()`](Self::wait), this
+	/// closes the child's stdin first, and the status is cached once collected.
+	///
+	/// This blocks until the whole group has exited, however long that takes. It must not be
+	/// called from an async task, as that would block the runtime's thread, and with it every
+	/// other task on it: use [`wait()`](Self::wait) there instead.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use command_group::AsyncGroupChild;
+	///
+	/// struct Service {
+	///     child: AsyncGroupChild,
+	/// }
+	///
+	/// impl Drop for Service {
+	///     fn drop(&mut self) {
+	///         if self.child.terminate().is_ok() {
+	///             self.child.wait_blocking().ok();
+	///         }
+	///     }
+	/// }
+	/// ```
+	pub fn wait_blocking(&mut self) -> Result<ExitStatus> {
+		if let Some(es) = self.exitstatus {
+			return Ok(es);
+		}
+
+		drop(self.imp.take_stdin());
+		let status = self.imp.wait_blocking().map_err(Error::wait)?;
+		self.exitstatus = Some(status);
+		Ok(status)
+	}
+
+	/// Waits for the child group to exit for up to `timeout`, killing it if it hasn't by then.
+	///
+	/// Returns `Ok(Some(status))` with the status that the process leader exited with if the group
+	/// finished in time, or `Ok(None)` if it had to be [`kill()`](Self::kill)ed. In that case, the
+	/// group has been waited on before returning, and its status is available from
+	/// [`wait()`](Self::wait).
+	///
+	/// Like `wait`, this closes the child's stdin first. The timeout is implemented with
+	/// [`tokio::time::timeout`], so this needs to be called from within a Tokio runtime with the
+	/// time driver enabled. To ask the group to exit before forcing it, see
+	/// [`terminate_then_kill()`](Self::terminate_then_kill).
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use std::time::Duration;
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let mut child = Command::new("make").arg("test").group_spawn().unwrap();
+	/// match child.wait_or_kill(Duration::from_secs(60)).await.expect("failed to wait for command") {
+	///     Some(status) => println!("exited with: {}", status),
+	///     None => println!("timed out"),
+	/// }
+	/// # }
+	/// ```
+	pub async fn wait_or_kill(&mut self, timeout: Duration) -> Result<Option<ExitStatus>> {
+		if let Ok(res) = tokio::time::timeout(timeout, self.wait()).await {
+			return res.map(Some);
+		}
+
+		ignore_exited(self.start_kill())?;
+		self.wait().await?;
+		Ok(None)
+	}
+
+	/// Asks the child group to exit, then forces it to if it hasn't done so within `grace`.
+	///
+	/// This first calls [`terminate()`](Self::terminate) and waits up to `grace` for the group to
+	/// exit. If it's still running after that, it is [`kill()`](Self::kill)ed and waited on. If the
+	/// group exits during the grace period, it is not killed.
+	///
+	/// Returns the status that the process leader exited with, either way. If the group had
+	/// already exited before this was called, that status is returned without signalling it.
+	///
+	/// The grace period is implemented with [`tokio::time::timeout`], so this needs to be called
+	/// from within a Tokio runtime with the time driver enabled.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use std::time::Duration;
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let mut child = Command::new("yes").group_spawn().unwrap();
+	/// let status = child.terminate_then_kill(Duration::from_secs(5)).await.expect("failed to stop command");
+	/// println!("exited with: {}", status);
+	/// # }
+	/// ```
+	pub async fn terminate_then_kill(&mut self, grace: Duration) -> Result<ExitStatus> {
+		if let Some(es) = self.try_wait()? {
+			return Ok(es);
+		}
+
+		ignore_exited(self.terminate())?;
+		if let Ok(res) = tokio::time::timeout(grace, self.wait()).await {
+			return res;
+		}
+
+		ignore_exited(self.start_kill())?;
+		self.wait().await
+	}
+
+	/// Returns a stream of the lines written to stdout and stderr, as they're produced.
+	///
+	/// This takes the stdout and stderr handles, if they were piped, and reads lines from both at
+	/// once. Each line is tagged with the stream it was read from, and has its line ending removed.
+	/// If only one of the streams was piped, only its lines are yielded. The stream ends once both
+	/// stdout and stderr have been closed, which usually happens when the group exits; the group
+	/// should still be waited on afterwards.
+	///
+	/// Lines are decoded as UTF-8, and a line that isn't valid UTF-8 yields an
+	/// [`InvalidData`](std::io::ErrorKind::InvalidData) error. Use
+	/// [`output_lines_bytes()`](Self::output_lines_bytes) to read lines as raw bytes instead.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use std::process::Stdio;
+	/// use tokio::process::Command;
+	/// use tokio_stream::StreamExt;
+	/// use command_group::{AsyncCommandGroup, OutputSource};
+	///
+	/// let mut child = Command::new("cargo")
+	///     .arg("build")
+	///     .stdout(Stdio::piped())
+	///     .stderr(Stdio::piped())
+	///     .group_spawn()
+	///     .expect("cargo command didn't start");
+	///
+	/// let mut lines = child.output_lines();
+	/// while let Some((source, line)) = lines.next().await.transpose().expect("failed to read") {
+	///     match source {
+	///         OutputSource::Stdout => println!("out: {line}"),
+	///         OutputSource::Stderr => println!("err: {line}"),
+	///     }
+	/// }
+	///
+	/// child.wait().await.expect("command wasn't running");
+	/// # }
+	/// ```
+	pub fn output_lines(
+		&mut self,
+	) -> impl Stream<Item = io::Result<(OutputSource, String)>> + Send + 'static {
+		fn lines<R>(reader: R) -> LinesStream<BufReader<R>>
+		where
+			R: tokio::io::AsyncRead + Unpin,
+		{
+			LinesStream::new(BufReader::new(reader).lines())
+		}
+
+		let stdout = self.imp.take_stdout().map(lines);
+		let stderr = self.imp.take_stderr().map(lines);
+		tag_stream(OutputSource::Stdout, stdout).merge(tag_stream(OutputSource::Stderr, stderr))
+	}
+
+	/// Returns a stream of the lines written to stdout and stderr, as raw bytes.
+	///
+	/// This is the same as [`output_lines()`](Self::output_lines), except that lines aren't
+	/// decoded, so output that isn't valid UTF-8 can be read too. Lines are split on `\n`, and a
+	/// trailing `\r` is also removed.
+	pub fn output_lines_bytes(
+		&mut self,
+	) -> impl Stream<Item = io::Result<(OutputSource, Vec<u8>)>> + Send + 'static {
+		fn split_lines<R>(reader: R) -> impl Stream<Item = io::Result<Vec<u8>>>
+		where
+			R: tokio::io::AsyncRead + Unpin,
+		{
+			SplitStream::new(BufReader::new(reader).split(b'\n')).map(|line| {
+				line.map(|mut line| {
+					if line.last() == Some(&b'\r') {
+						line.pop();
+					}
+					line
+				})
+			})
+		}
+
+		let stdout = self.imp.take_stdout().map(split_lines);
+		let stderr = self.imp.take_stderr().map(split_lines);
+		tag_stream(OutputSource::Stdout, stdout).merge(tag_stream(OutputSource::Stderr, stderr))
+	}
+
+	/// Simultaneously waits for the child to exit and collect all remaining output on the
+	/// stdout/stderr handles, returning an `Output` instance.
+	///
+	/// See [the Tokio documentation](Child::wait_with_output) for more.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```should_panic
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use std::process::Stdio;
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let child = Command::new("/bin/cat")
+	///     .arg("file.txt")
+	///     .stdout(Stdio::piped())
+	///     .group_spawn()
+	///     .expect("failed to execute child");
+	///
+	/// let output = child
+	///     .wait_with_output()
+	///     .await
+	///     .expect("failed to wait on child");
+	///
+	/// assert!(output.status.success());
+	/// # }
+	/// ```
+	pub async fn wait_with_output(mut self) -> Result<Output> {
+		drop(self.imp.take_stdin());
 
 		let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
 		match (self.imp.take_stdout(), self.imp.take_stderr()) {
@@ -352,18 +1039,374 @@ impl AsyncGroupChild {
 			}
 		}
 
-		let status = self.imp.wait().await?;
+		let status = self.imp.wait().await.map_err(Error::wait)?;
 		Ok(Output {
 			status,
 			stdout,
 			stderr,
 		})
 	}
+
+	/// Writes `input` to the child's stdin and closes it, while collecting all output and waiting
+	/// for the child to exit, like [`wait_with_output()`](Self::wait_with_output).
+	///
+	/// The input is written concurrently with reading the output, so that the child can't block
+	/// writing its output while this is waiting to write its input. If the child exits or closes
+	/// its stdin before reading all of the input, the rest is discarded.
+	///
+	/// STDIN must be piped, unless `input` is empty. STDOUT and STDERR are collected if piped.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use std::process::Stdio;
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let child = Command::new("sort")
+	///     .stdin(Stdio::piped())
+	///     .stdout(Stdio::piped())
+	///     .group_spawn()
+	///     .expect("failed to execute child");
+	///
+	/// let output = child
+	///     .communicate(b"b\na\n")
+	///     .await
+	///     .expect("failed to communicate with child");
+	///
+	/// assert_eq!(output.stdout, b"a\nb\n");
+	/// # }
+	/// ```
+	pub async fn communicate(mut self, input: &[u8]) -> Result<Output> {
+		let stdin = self.imp.take_stdin();
+		if stdin.is_none() && !input.is_empty() {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"the child's stdin is not piped",
+			)
+			.into());
+		}
+
+		let write = async move {
+			match stdin {
+				Some(mut stdin) => ignore_broken_pipe(stdin.write_all(input).await),
+				None => Ok(()),
+			}
+		};
+		let (written, output) = tokio::join!(write, self.wait_with_output());
+		let output = output?;
+		written?;
+		Ok(output)
+	}
+
+	/// Waits for the child to exit, passing each line of its output to `f` as it's read.
+	///
+	/// This is like [`wait_with_output()`](Self::wait_with_output), but rather than collecting the
+	/// output until the child exits, it's handed over line by line. Each [`OutputLine`] records
+	/// whether it came from STDOUT or STDERR. Lines from the two streams are passed in the order
+	/// they're read, and a partial line is held on to until it's completed, or until its stream is
+	/// closed. Only piped streams are read.
+	///
+	/// Like `wait_with_output`, this closes the child's stdin first, and returns once the output
+	/// has closed and the whole group has exited. To process the output asynchronously instead,
+	/// see [`output_lines_bytes()`](Self::output_lines_bytes).
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use std::process::Stdio;
+	/// use tokio::process::Command;
+	/// use command_group::{AsyncCommandGroup, OutputSource};
+	///
+	/// let child = Command::new("cargo")
+	///     .arg("build")
+	///     .stdout(Stdio::piped())
+	///     .stderr(Stdio::piped())
+	///     .group_spawn()
+	///     .expect("cargo command didn't start");
+	///
+	/// let status = child
+	///     .stream_output(|output| {
+	///         let line = String::from_utf8_lossy(&output.line);
+	///         match output.source {
+	///             OutputSource::Stdout => println!("out: {line}"),
+	///             OutputSource::Stderr => println!("err: {line}"),
+	///         }
+	///     })
+	///     .await
+	///     .expect("failed to wait on child");
+	///
+	/// assert!(status.success());
+	/// # }
+	/// ```
+	pub async fn stream_output<F: FnMut(OutputLine)>(mut self, mut f: F) -> Result<ExitStatus> {
+		drop(self.imp.take_stdin());
+
+		let mut lines = self.output_lines_bytes();
+		while let Some((source, line)) = lines.next().await.transpose()? {
+			f(OutputLine { source, line });
+		}
+
+		self.imp.wait().await.map_err(Error::wait)
+	}
+
+	/// Like [`wait_with_output()`](Self::wait_with_output), but stops collecting each of STDOUT
+	/// and STDERR once it reaches its size limit, to bound memory use.
+	///
+	/// Past its limit, a stream is still read until it closes, but what's read is discarded, so the
+	/// child doesn't block on a full pipe. The returned [`LimitedOutput`] records which streams
+	/// were cut short. As with `wait_with_output`, this waits for the streams to close and the
+	/// group to exit, so a group which never stops writing has to be stopped some other way, such
+	/// as with a [`kill_handle()`](Self::kill_handle).
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use std::process::Stdio;
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let child = Command::new("cat")
+	///     .arg("big.log")
+	///     .stdout(Stdio::piped())
+	///     .group_spawn()
+	///     .expect("failed to execute child");
+	///
+	/// let limited = child
+	///     .wait_with_output_limited(1024, 1024)
+	///     .await
+	///     .expect("failed to wait on child");
+	///
+	/// if limited.stdout_truncated {
+	///     println!("only showing the first KiB of the log");
+	/// }
+	/// # }
+	/// ```
+	pub async fn wait_with_output_limited(
+		mut self,
+		max_stdout: usize,
+		max_stderr: usize,
+	) -> Result<LimitedOutput> {
+		drop(self.imp.take_stdin());
+
+		let ((stdout, stdout_truncated), (stderr, stderr_truncated)) = tokio::try_join!(
+			read_limited(self.imp.take_stdout(), max_stdout),
+			read_limited(self.imp.take_stderr(), max_stderr),
+		)?;
+
+		let status = self.wait().await?;
+		Ok(LimitedOutput {
+			output: Output {
+				status,
+				stdout,
+				stderr,
+			},
+			stdout_truncated,
+			stderr_truncated,
+		})
+	}
+
+	/// Collects all output and waits for the child group to exit, like
+	/// [`wait_with_output()`](Self::wait_with_output), but for at most `timeout`.
+	///
+	/// If the group is still running once the time runs out, it is [`kill()`](Self::kill)ed. The
+	/// output it wrote until then is still returned: its pipes are read until they close, which
+	/// happens as the group dies, and then the group is waited on. The returned [`TimedOutput`]
+	/// records whether that happened. As with `wait_with_output()`, a process which left the group
+	/// while holding on to the pipes keeps this from returning until it closes them.
+	///
+	/// The timeout is implemented with Tokio's timers, so this needs to be called from within a
+	/// Tokio runtime with the time driver enabled.
+	///
+	/// # Examples
+	///
+	/// Basic usage:
+	///
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// use std::{process::Stdio, time::Duration};
+	/// use tokio::process::Command;
+	/// use command_group::AsyncCommandGroup;
+	///
+	/// let child = Command::new("make")
+	///     .arg("test")
+	///     .stdout(Stdio::piped())
+	///     .group_spawn()
+	///     .expect("make command didn't start");
+	/// let timed = child
+	///     .wait_with_output_timeout(Duration::from_secs(60))
+	///     .await
+	///     .expect("failed to wait on child");
+	///
+	/// if timed.timed_out {
+	///     println!("gave up on the tests, output so far:");
+	/// }
+	/// println!("{}", String::from_utf8_lossy(&timed.output.stdout));
+	/// # }
+	/// ```
+	pub async fn wait_with_output_timeout(mut self, timeout: Duration) -> Result<TimedOutput> {
+		drop(self.imp.take_stdin());
+		let deadline = tokio::time::Instant::now() + timeout;
+		let mut timed_out = false;
+
+		let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+		let (out, err) = (self.imp.take_stdout(), self.imp.take_stderr());
+		{
+			let read =
+				async { tokio::try_join!(read_all(out, &mut stdout), read_all(err, &mut stderr)) };
+			tokio::pin!(read);
+
+			// on timeout, keep reading rather than dropping the read, so what was written isn't lost
+			match tokio::time::timeout_at(deadline, &mut read).await {
+				Ok(res) => res?,
+				Err(_) => {
+					timed_out = true;
+					ignore_exited(self.start_kill())?;
+					read.await?
+				}
+			};
+		}
+
+		let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+		let status = match self.wait_or_kill(remaining).await? {
+			Some(status) => status,
+			None => {
+				timed_out = true;
+				self.wait().await?
+			}
+		};
+
+		Ok(TimedOutput {
+			output: Output {
+				status,
+				stdout,
+				stderr,
+			},
+			timed_out,
+		})
+	}
+}
+
+/// Reads a stream to its end, if there is one.
+async fn read_all(stream: Option<impl AsyncRead + Unpin>, data: &mut Vec<u8>) -> io::Result<()> {
+	if let Some(mut stream) = stream {
+		stream.read_to_end(data).await?;
+	}
+	Ok(())
+}
+
+/// Reads a stream to its end, keeping at most `max` bytes, and whether any more were discarded.
+///
+/// If there's no stream, there's nothing to read.
+async fn read_limited(
+	stream: Option<impl AsyncRead + Unpin>,
+	max: usize,
+) -> io::Result<(Vec<u8>, bool)> {
+	let mut data = Vec::new();
+	let mut truncated = false;
+	let Some(mut stream) = stream else {
+		return Ok((data, truncated));
+	};
+
+	let mut buf = [0; 8192];
+	loop {
+		let n = stream.read(&mut buf).await?;
+		if n == 0 {
+			return Ok((data, truncated));
+		}
+
+		let take = n.min(max - data.len());
+		data.extend_from_slice(&buf[..take]);
+		truncated |= take < n;
+	}
+}
+
+type TaggedStream<T> = Pin<Box<dyn Stream<Item = io::Result<(OutputSource, T)>> + Send>>;
+
+/// Tags each item of an output stream with its source, or yields nothing if there's no stream.
+fn tag_stream<T, S>(source: OutputSource, stream: Option<S>) -> TaggedStream<T>
+where
+	T: Send + 'static,
+	S: Stream<Item = io::Result<T>> + Send + 'static,
+{
+	match stream {
+		Some(stream) => Box::pin(stream.map(move |item| item.map(|item| (source, item)))),
+		None => Box::pin(tokio_stream::empty()),
+	}
+}
+
+/// Awaiting a mutable reference to the child is the same as calling [`wait()`](AsyncGroupChild::wait).
+///
+/// This makes it possible to use the child directly in `select!` or `join!`, while keeping it
+/// around afterwards. As with `wait()`, the exit status is cached, so awaiting again once the group
+/// has exited returns the same status.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// use tokio::process::Command;
+/// use command_group::AsyncCommandGroup;
+///
+/// let mut child = Command::new("ls").group_spawn().expect("ls command didn't start");
+/// let status = (&mut child).await.expect("command wasn't running");
+/// println!("Child exited with {status}");
+/// # }
+/// ```
+impl<'a> IntoFuture for &'a mut AsyncGroupChild {
+	type Output = Result<ExitStatus>;
+	type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send + 'a>>;
+
+	fn into_future(self) -> Self::IntoFuture {
+		Box::pin(self.wait())
+	}
+}
+
+/// Awaiting the child is the same as calling [`wait()`](AsyncGroupChild::wait), and then dropping
+/// the child.
+///
+/// This makes the child usable wherever a future is expected, for example with combinators. To
+/// keep the child after it has exited, await a mutable reference to it instead.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// use tokio::process::Command;
+/// use command_group::AsyncCommandGroup;
+///
+/// let child = Command::new("ls").group_spawn().expect("ls command didn't start");
+/// let status = child.await.expect("command wasn't running");
+/// println!("Child exited with {status}");
+/// # }
+/// ```
+impl IntoFuture for AsyncGroupChild {
+	type Output = Result<ExitStatus>;
+	type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+	fn into_future(mut self) -> Self::IntoFuture {
+		Box::pin(async move { self.wait().await })
+	}
 }
 
 #[cfg(unix)]
 impl crate::UnixChildExt for AsyncGroupChild {
 	fn signal(&self, sig: Signal) -> Result<()> {
-		self.imp.signal_imp(sig)
+		self.imp.signal_imp(sig).map_err(Error::signal)
 	}
 }
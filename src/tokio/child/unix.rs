@@ -1,40 +1,74 @@
 use std::{
 	convert::TryInto,
 	io::{Error, Result},
+	mem,
 	ops::ControlFlow,
 	os::unix::process::ExitStatusExt,
 	process::ExitStatus,
+	time::Duration,
 };
 
+use crate::{error::kill_error, procs::group_pids, GroupKill};
 use nix::{
 	errno::Errno,
 	libc,
 	sys::{
-		signal::{killpg, Signal},
+		signal::{kill, killpg, Signal},
 		wait::WaitPidFlag,
 	},
 	unistd::Pid,
 };
+#[cfg(all(feature = "pidfd", target_os = "linux"))]
+use tokio::io::{unix::AsyncFd, Interest};
 use tokio::{
 	process::{Child, ChildStderr, ChildStdin, ChildStdout},
-	task::spawn_blocking,
+	time::sleep,
 };
 
 pub(super) struct ChildImp {
 	pgid: Pid,
+	leader: Pid,
 	inner: Child,
+	kill_on_drop: Option<KillOnDrop>,
+}
+
+/// Signals the process group when dropped.
+///
+/// This is disarmed once the group leader has been reaped: past that point the process group ID
+/// may be reused by the kernel, and the group is most likely gone anyway.
+struct KillOnDrop(Pid, Signal);
+
+impl KillOnDrop {
+	fn disarm(self) {
+		mem::forget(self);
+	}
+}
+
+impl Drop for KillOnDrop {
+	fn drop(&mut self) {
+		// nothing useful can be done with an error here
+		killpg(self.0, self.1).ok();
+	}
 }
 
 impl ChildImp {
-	pub(super) fn new(inner: Child) -> Self {
+	pub(super) fn new(inner: Child, process_group: i32, kill_on_drop: Option<Signal>) -> Self {
 		let pid = inner
 			.id()
 			.expect("Command was reaped before we could read its PID")
 			.try_into()
 			.expect("Command PID > i32::MAX");
+		let leader = Pid::from_raw(pid);
+		let pgid = if process_group == 0 {
+			leader
+		} else {
+			Pid::from_raw(process_group)
+		};
 		Self {
-			pgid: Pid::from_raw(pid),
+			pgid,
+			leader,
 			inner,
+			kill_on_drop: kill_on_drop.map(|sig| KillOnDrop(pgid, sig)),
 		}
 	}
 
@@ -54,22 +88,75 @@ impl ChildImp {
 		&mut self.inner
 	}
 
-	pub fn into_inner(self) -> Child {
+	pub(super) fn inner_ref(&self) -> &Child {
+		&self.inner
+	}
+
+	pub fn into_inner(mut self) -> Child {
+		// the caller takes over responsibility for the process
+		self.disarm_kill_on_drop();
 		self.inner
 	}
 
+	pub fn detach(self) {
+		// the Child doesn't do anything to the process when dropped
+		drop(self.into_inner());
+	}
+
+	fn disarm_kill_on_drop(&mut self) {
+		if let Some(guard) = self.kill_on_drop.take() {
+			guard.disarm();
+		}
+	}
+
+	pub(super) fn kill_handle(&self) -> Result<GroupKill> {
+		Ok(GroupKill::new(self.pgid))
+	}
+
 	pub(super) fn signal_imp(&self, sig: Signal) -> Result<()> {
-		killpg(self.pgid, sig).map_err(Error::from)
+		killpg(self.pgid, sig).map_err(kill_error)
+	}
+
+	pub fn signal_leader(&self, sig: Signal) -> Result<()> {
+		// once reaped, the leader's pid may belong to an unrelated process
+		if self.inner.id().is_none() {
+			return Err(crate::Error::AlreadyExited.into());
+		}
+
+		kill(self.leader, sig).map_err(kill_error)
 	}
 
 	pub fn start_kill(&mut self) -> Result<()> {
 		self.signal_imp(Signal::SIGKILL)
 	}
 
+	pub fn terminate(&mut self) -> Result<()> {
+		self.signal_imp(Signal::SIGTERM)
+	}
+
 	pub fn id(&self) -> Option<u32> {
 		self.inner.id()
 	}
 
+	pub(super) fn process_count(&self) -> Result<usize> {
+		group_pids(self.pgid).map(|pids| pids.len())
+	}
+
+	pub(super) fn pids(&self) -> Result<Vec<u32>> {
+		let pids = group_pids(self.pgid)?;
+		Ok(pids.into_iter().map(|pid| pid.as_raw() as u32).collect())
+	}
+
+	pub fn group_id(&self) -> Option<u32> {
+		// like id(), don't hand out an expired (and possibly reused) id
+		self.inner.id().map(|_| self.pgid.as_raw() as u32)
+	}
+
+	/// Whether the child leads its group, rather than having joined an existing one.
+	fn owns_group(&self) -> bool {
+		self.pgid == self.leader
+	}
+
 	fn wait_imp(pgid: i32, flag: WaitPidFlag) -> Result<ControlFlow<Option<ExitStatus>>> {
 		// Wait for processes in a loop until every process in this
 		// process group has exited (this ensures that we reap any
@@ -95,6 +182,8 @@ impl ChildImp {
 							// graceful exit.
 							return Ok(ControlFlow::Break(parent_exit_status));
 						}
+						// only when blocking, and that's no reason to stop waiting
+						Errno::EINTR => continue,
 						errno => {
 							return Err(Error::from(errno));
 						}
@@ -116,39 +205,119 @@ impl ChildImp {
 	}
 
 	pub async fn wait(&mut self) -> Result<ExitStatus> {
-		const MAX_RETRY_ATTEMPT: usize = 10;
+		const MAX_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 		// Always wait for parent to exit first.
 		//
 		// It's likely that all its children has already exited and reaped by
 		// the time the parent exits.
+		#[cfg(all(feature = "pidfd", target_os = "linux"))]
+		self.wait_leader_pidfd().await?;
 		let status = self.inner.wait().await?;
+		self.disarm_kill_on_drop();
+
+		// If the child joined an existing group, that group may contain other children of ours,
+		// which we have no business reaping.
+		if !self.owns_group() {
+			return Ok(status);
+		}
 
 		let pgid = self.pgid.as_raw();
 
-		// Try reaping all children, if there are some that are still alive after
-		// several attempts, then spawn a blocking task to reap them.
-		for retry_attempt in 1..=MAX_RETRY_ATTEMPT {
-			if Self::wait_imp(pgid, WaitPidFlag::WNOHANG)?.is_break() {
-				break;
-			} else if retry_attempt == MAX_RETRY_ATTEMPT {
-				spawn_blocking(move || Self::wait_imp(pgid, WaitPidFlag::empty())).await??;
-			}
+		// Reap all children, polling with a backoff while some are still alive. Unlike a blocking
+		// waitpid() on another thread, this stops as soon as the future is dropped.
+		let mut interval = Duration::from_millis(1);
+		while Self::wait_imp(pgid, WaitPidFlag::WNOHANG)?.is_continue() {
+			sleep(interval).await;
+			interval = (interval * 2).min(MAX_POLL_INTERVAL);
 		}
 
 		Ok(status)
 	}
 
+	/// Waits for the leader to exit by polling a pidfd with the reactor.
+	///
+	/// This doesn't reap the leader: once this returns, `Child::wait()` can do so immediately. If
+	/// the kernel doesn't support pidfds, or the leader was already reaped, this returns at once.
+	#[cfg(all(feature = "pidfd", target_os = "linux"))]
+	async fn wait_leader_pidfd(&self) -> Result<()> {
+		let Some(fd) = self.inner.id().and_then(crate::pidfd::pidfd_open) else {
+			return Ok(());
+		};
+
+		// a pidfd only ever becomes readable once, when the process exits
+		let fd = AsyncFd::with_interest(fd, Interest::READABLE)?;
+		fd.readable().await?.retain_ready();
+		Ok(())
+	}
+
+	/// Waits for the group to exit by blocking the thread, without going through Tokio.
+	///
+	/// The leader is reaped here, behind Tokio's back, so its status must be kept by the caller.
+	pub fn wait_blocking(&mut self) -> Result<ExitStatus> {
+		let status = if self.owns_group() {
+			match Self::wait_imp(self.pgid.as_raw(), WaitPidFlag::empty())? {
+				ControlFlow::Break(status) => status,
+				// only returned with WNOHANG
+				ControlFlow::Continue(()) => None,
+			}
+		} else {
+			wait_leader_blocking(self.leader)?
+		};
+		self.disarm_kill_on_drop();
+
+		match status {
+			Some(status) => Ok(status),
+			// Tokio had already reaped the leader, and keeps its status
+			None => self
+				.inner
+				.try_wait()?
+				.ok_or_else(|| Error::from(Errno::ECHILD)),
+		}
+	}
+
 	pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
-		match Self::wait_imp(self.pgid.as_raw(), WaitPidFlag::WNOHANG)? {
-			ControlFlow::Break(res) => Ok(res),
-			ControlFlow::Continue(()) => self.inner.try_wait(),
+		let reaped = if self.owns_group() {
+			Self::wait_imp(self.pgid.as_raw(), WaitPidFlag::WNOHANG)?
+		} else {
+			ControlFlow::Continue(())
+		};
+
+		let status = match reaped {
+			ControlFlow::Break(res) => {
+				// the whole group is gone, even if the leader's status was collected elsewhere
+				self.disarm_kill_on_drop();
+				return Ok(res);
+			}
+			ControlFlow::Continue(()) => self.inner.try_wait()?,
+		};
+
+		if status.is_some() {
+			self.disarm_kill_on_drop();
+		}
+		Ok(status)
+	}
+}
+
+/// Blocks until the leader exits and reaps it, or returns `None` if it was already reaped.
+fn wait_leader_blocking(leader: Pid) -> Result<Option<ExitStatus>> {
+	let mut status: i32 = 0;
+	loop {
+		// as in wait_imp(), the raw status is needed to build an ExitStatus
+		if unsafe { libc::waitpid(leader.into(), &mut status as *mut libc::c_int, 0) } != -1 {
+			return Ok(Some(ExitStatus::from_raw(status)));
+		}
+
+		match Errno::last() {
+			Errno::EINTR => continue,
+			Errno::ECHILD => return Ok(None),
+			errno => return Err(Error::from(errno)),
 		}
 	}
 }
 
 impl crate::UnixChildExt for ChildImp {
-	fn signal(&self, sig: Signal) -> Result<()> {
-		self.signal_imp(sig)
+	fn signal(&self, sig: Signal) -> crate::Result<()> {
+		self.signal_imp(sig).map_err(crate::Error::signal)
 	}
 }
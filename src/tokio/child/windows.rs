@@ -1,34 +1,28 @@
-use std::{io::Result, mem, ops::ControlFlow, process::ExitStatus};
+use crate::{winres::*, ControlEvent, GroupKill};
+use std::{
+	io::{Error, Result},
+	os::windows::io::{AsHandle, AsRawHandle, BorrowedHandle, IntoRawHandle},
+	process::ExitStatus,
+	time::Duration,
+};
 use tokio::{
 	process::{Child, ChildStderr, ChildStdin, ChildStdout},
-	task::spawn_blocking,
-};
-use winapi::{
-	shared::{
-		basetsd::ULONG_PTR,
-		minwindef::{DWORD, FALSE},
-	},
-	um::{
-		handleapi::CloseHandle, ioapiset::GetQueuedCompletionStatus, jobapi2::TerminateJobObject,
-		minwinbase::OVERLAPPED, winbase::INFINITE, winnt::HANDLE,
-	},
+	time::sleep,
 };
-
-use crate::winres::*;
+use winapi::um::winbase::INFINITE;
 
 pub(super) struct ChildImp {
 	inner: Child,
 	handles: JobPort,
+	new_process_group: bool,
 }
 
 impl ChildImp {
-	pub fn new(inner: Child, job: HANDLE, completion_port: HANDLE) -> Self {
+	pub fn new(inner: Child, handles: JobPort, new_process_group: bool) -> Self {
 		Self {
 			inner,
-			handles: JobPort {
-				job,
-				completion_port,
-			},
+			handles,
+			new_process_group,
 		}
 	}
 
@@ -48,54 +42,96 @@ impl ChildImp {
 		&mut self.inner
 	}
 
-	pub fn into_inner(self) -> Child {
-		let its = mem::ManuallyDrop::new(self.handles);
+	pub(super) fn inner_ref(&self) -> &Child {
+		&self.inner
+	}
 
-		// manually drop the completion port
-		unsafe { CloseHandle(its.completion_port) };
+	pub fn into_inner(self) -> Child {
+		let JobPort {
+			job,
+			completion_port,
+		} = self.handles;
+		drop(completion_port);
 		// we leave the job handle unclosed, otherwise the Child is useless
 		// (as closing it will terminate the job)
+		let _ = job.into_raw_handle();
 
 		self.inner
 	}
 
+	pub fn detach(self) {
+		let JobPort {
+			job,
+			completion_port,
+		} = self.handles;
+		drop(completion_port);
+		// closing the job would terminate it if it's set to kill on close; if that can't be
+		// cleared, leave the handle unclosed, so that only happens when this process exits
+		if disarm_kill_on_close(job.as_raw_handle() as _).is_err() {
+			let _ = job.into_raw_handle();
+		}
+	}
+
 	pub fn start_kill(&mut self) -> Result<()> {
-		res_bool(unsafe { TerminateJobObject(self.handles.job, 1) })
+		self.start_kill_with_code(1)
 	}
 
-	pub fn id(&self) -> Option<u32> {
-		self.inner.id()
+	pub fn start_kill_with_code(&mut self, code: u32) -> Result<()> {
+		terminate_job(self.handles.raw_job(), code)
 	}
 
-	fn wait_imp(completion_port: ThreadSafeRawHandle, timeout: DWORD) -> Result<ControlFlow<()>> {
-		let mut code: DWORD = 0;
-		let mut key: ULONG_PTR = 0;
-		let mut overlapped = mem::MaybeUninit::<OVERLAPPED>::uninit();
-		let mut lp_overlapped = overlapped.as_mut_ptr();
-
-		let result = unsafe {
-			GetQueuedCompletionStatus(
-				completion_port.0,
-				&mut code,
-				&mut key,
-				&mut lp_overlapped,
-				timeout,
-			)
-		};
-
-		// ignore timing out errors unless the timeout was specified to INFINITE
-		// https://docs.microsoft.com/en-us/windows/win32/api/ioapiset/nf-ioapiset-getqueuedcompletionstatus
-		if timeout != INFINITE && result == FALSE && lp_overlapped.is_null() {
-			return Ok(ControlFlow::Continue(()));
+	pub fn terminate(&mut self) -> Result<()> {
+		if self.send_control(ControlEvent::CtrlBreak).is_ok() {
+			return Ok(());
 		}
 
-		res_bool(result)?;
+		self.start_kill()
+	}
+
+	pub fn send_control(&self, event: ControlEvent) -> Result<()> {
+		send_control(
+			self.handles.raw_job(),
+			self.inner.id(),
+			self.new_process_group,
+			event,
+		)
+	}
+
+	pub(super) fn kill_handle(&self) -> Result<GroupKill> {
+		GroupKill::new(&self.handles.job)
+	}
+
+	pub(super) fn job_handle(&self) -> BorrowedHandle<'_> {
+		self.handles.job.as_handle()
+	}
 
-		Ok(ControlFlow::Break(()))
+	pub(super) fn completion_port_handle(&self) -> BorrowedHandle<'_> {
+		self.handles.completion_port.as_handle()
+	}
+
+	pub(super) fn process_count(&self) -> Result<usize> {
+		job_active_processes(self.handles.raw_job()).map(|count| count as usize)
+	}
+
+	pub(super) fn pids(&self) -> Result<Vec<u32>> {
+		job_pids(self.handles.raw_job())
+	}
+
+	pub(super) fn job_accounting(&self) -> Result<crate::JobAccounting> {
+		job_accounting(self.handles.raw_job())
+	}
+
+	pub fn id(&self) -> Option<u32> {
+		self.inner.id()
+	}
+
+	pub fn group_id(&self) -> Option<u32> {
+		// there are no process groups, so use the leader as the closest equivalent
+		self.inner.id()
 	}
 
 	pub async fn wait(&mut self) -> Result<ExitStatus> {
-		const MAX_RETRY_ATTEMPT: usize = 10;
+		const MAX_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 		// Always wait for parent to exit first.
 		//
@@ -103,23 +139,28 @@ impl ChildImp {
 		// the time the parent exits.
 		let status = self.inner.wait().await?;
 
-		let completion_port = ThreadSafeRawHandle(self.handles.completion_port);
-
-		// Try waiting for group exit, if it is still alive after several
-		// attempts, then spawn a blocking task to reap them.
-		for retry_attempt in 1..=MAX_RETRY_ATTEMPT {
-			if Self::wait_imp(completion_port, 0)?.is_break() {
-				break;
-			} else if retry_attempt == MAX_RETRY_ATTEMPT {
-				spawn_blocking(move || Self::wait_imp(completion_port, INFINITE)).await??;
-			}
+		// Wait for group exit, polling with a backoff. Unlike a blocking wait on another thread,
+		// this stops as soon as the future is dropped.
+		let mut interval = Duration::from_millis(1);
+		while !wait_job_empty(&self.handles, 0)? {
+			sleep(interval).await;
+			interval = (interval * 2).min(MAX_POLL_INTERVAL);
 		}
 
 		Ok(status)
 	}
 
+	/// Waits for the group to exit by blocking the thread, without going through Tokio.
+	pub fn wait_blocking(&mut self) -> Result<ExitStatus> {
+		wait_job_empty(&self.handles, INFINITE)?;
+		// the leader is in the job, so it has exited too
+		self.inner
+			.try_wait()?
+			.ok_or_else(|| Error::other("the group's leader is still running"))
+	}
+
 	pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
-		Self::wait_imp(ThreadSafeRawHandle(self.handles.completion_port), 0)?;
+		wait_job_empty(&self.handles, 0)?;
 		self.inner.try_wait()
 	}
 }
@@ -1,7 +1,6 @@
-use std::{
-	io::Result,
-	process::{ExitStatus, Output},
-};
+use std::process::{ExitStatus, Output};
+
+use crate::{Error, Result};
 
 use super::AsyncGroupChild;
 use tokio::process::Child;
@@ -10,6 +9,23 @@ use tokio::process::Child;
 ///
 /// This is a helper which erases that a [`tokio::process::Child`] is a different type than an
 /// [`AsyncGroupChild`]. It forwards to the corresponding method on the inner type.
+///
+/// It can be created from either with `.into()`:
+///
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// use tokio::process::Command;
+/// use command_group::{AsyncCommandGroup, tokio::ErasedChild};
+///
+/// let grouped = true;
+/// let child: ErasedChild = if grouped {
+///     Command::new("ls").group_spawn().expect("ls command didn't start").into()
+/// } else {
+///     Command::new("ls").spawn().expect("ls command didn't start").into()
+/// };
+/// # }
+/// ```
 #[derive(Debug)]
 pub enum ErasedChild {
 	/// A grouped process child.
@@ -20,11 +36,39 @@ pub enum ErasedChild {
 }
 
 impl ErasedChild {
+	/// Returns a mutable reference to the inner Tokio [`Child`].
+	///
+	/// For a grouped child, the same caveats as [`AsyncGroupChild::inner`] apply: the inner child
+	/// may not be in the same state as the wrapper, due to how methods like `wait` and `kill` are
+	/// implemented.
+	///
+	/// - Grouped: [`AsyncGroupChild::inner`]
+	/// - Ungrouped: the child itself
+	pub fn inner(&mut self) -> &mut Child {
+		match self {
+			Self::Grouped(c) => c.inner(),
+			Self::Ungrouped(c) => c,
+		}
+	}
+
+	/// Consumes itself and returns the inner Tokio [`Child`].
+	///
+	/// For a grouped child, the same caveats as [`AsyncGroupChild::into_inner`] apply.
+	///
+	/// - Grouped: [`AsyncGroupChild::into_inner`]
+	/// - Ungrouped: the child itself
+	pub fn into_inner(self) -> Child {
+		match self {
+			Self::Grouped(c) => c.into_inner(),
+			Self::Ungrouped(c) => c,
+		}
+	}
+
 	/// Returns the OS-assigned process (group) identifier.
 	///
 	/// - Grouped: [`AsyncGroupChild::id`]
 	/// - Ungrouped: [`Child::id`]
-	pub fn id(&mut self) -> Option<u32> {
+	pub fn id(&self) -> Option<u32> {
 		match self {
 			Self::Grouped(c) => c.id(),
 			Self::Ungrouped(c) => c.id(),
@@ -38,7 +82,7 @@ impl ErasedChild {
 	pub async fn kill(&mut self) -> Result<()> {
 		match self {
 			Self::Grouped(c) => c.kill().await,
-			Self::Ungrouped(c) => c.kill().await,
+			Self::Ungrouped(c) => c.kill().await.map_err(Error::signal),
 		}
 	}
 
@@ -49,7 +93,7 @@ impl ErasedChild {
 	pub fn start_kill(&mut self) -> Result<()> {
 		match self {
 			Self::Grouped(c) => c.start_kill(),
-			Self::Ungrouped(c) => c.start_kill(),
+			Self::Ungrouped(c) => c.start_kill().map_err(Error::signal),
 		}
 	}
 
@@ -60,7 +104,7 @@ impl ErasedChild {
 	pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
 		match self {
 			Self::Grouped(c) => c.try_wait(),
-			Self::Ungrouped(c) => c.try_wait(),
+			Self::Ungrouped(c) => c.try_wait().map_err(Error::wait),
 		}
 	}
 
@@ -71,7 +115,7 @@ impl ErasedChild {
 	pub async fn wait(&mut self) -> Result<ExitStatus> {
 		match self {
 			Self::Grouped(c) => c.wait().await,
-			Self::Ungrouped(c) => c.wait().await,
+			Self::Ungrouped(c) => c.wait().await.map_err(Error::wait),
 		}
 	}
 
@@ -82,7 +126,7 @@ impl ErasedChild {
 	pub async fn wait_with_output(self) -> Result<Output> {
 		match self {
 			Self::Grouped(c) => c.wait_with_output().await,
-			Self::Ungrouped(c) => c.wait_with_output().await,
+			Self::Ungrouped(c) => c.wait_with_output().await.map_err(Error::wait),
 		}
 	}
 
@@ -99,4 +143,40 @@ impl ErasedChild {
 			Self::Ungrouped(c) => c.signal(sig),
 		}
 	}
+
+	/// Sends a signal to the process, as far as Windows supports it.
+	///
+	/// Windows has no signals, so only [`Kill`](crate::Signal::Kill) and
+	/// [`Terminate`](crate::Signal::Terminate) are supported, and both forcibly terminate the
+	/// child. Other signals return an [`Unsupported`](std::io::ErrorKind::Unsupported) error.
+	///
+	/// If an ungrouped child has already been waited on, this does nothing.
+	///
+	/// - Grouped: [`GroupKill::kill`](crate::GroupKill::kill)
+	/// - Ungrouped: `TerminateProcess`
+	#[cfg(windows)]
+	pub fn signal(&self, sig: crate::Signal) -> Result<()> {
+		match (self, sig) {
+			(Self::Grouped(c), crate::Signal::Kill | crate::Signal::Terminate) => {
+				c.kill_handle()?.kill()
+			}
+			(Self::Grouped(_), sig) => Err(Error::signal(crate::winres::unsupported_signal(sig))),
+			(Self::Ungrouped(c), sig) => match c.raw_handle() {
+				Some(handle) => crate::winres::signal_process(handle, sig).map_err(Error::signal),
+				None => Ok(()),
+			},
+		}
+	}
+}
+
+impl From<AsyncGroupChild> for ErasedChild {
+	fn from(child: AsyncGroupChild) -> Self {
+		Self::Grouped(child)
+	}
+}
+
+impl From<Child> for ErasedChild {
+	fn from(child: Child) -> Self {
+		Self::Ungrouped(child)
+	}
 }
@@ -0,0 +1,105 @@
+use std::{fmt, panic::resume_unwind, process::ExitStatus};
+
+use tokio::task::JoinSet;
+
+use crate::{AsyncGroupChild, Result};
+
+/// A set of process groups, which can be waited on together.
+///
+/// This owns several [`AsyncGroupChild`]ren, each under a key chosen by the caller, and resolves
+/// [`next_exited()`](Self::next_exited) as soon as any of them has exited. It saves supervisors
+/// from polling each group in turn, or from spawning a task per group just to wait on it.
+///
+/// Each group is waited on by its own task, which owns the child from when it's inserted until it
+/// has exited. To signal or kill groups while they're in the set, take a
+/// [`kill_handle()`](AsyncGroupChild::kill_handle) before inserting them. Dropping the set aborts
+/// those tasks, which drops the children that haven't exited yet.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// use tokio::process::Command;
+/// use command_group::{AsyncCommandGroup, tokio::GroupSet};
+///
+/// let mut set = GroupSet::new();
+/// for name in ["web", "worker"] {
+///     let child = Command::new(name).group_spawn().expect("command didn't start");
+///     set.insert(name, child);
+/// }
+///
+/// while let Some((name, status)) = set.next_exited().await {
+///     println!("{name} exited with: {:?}", status);
+/// }
+/// # }
+/// ```
+pub struct GroupSet<K> {
+	waits: JoinSet<(K, Result<ExitStatus>)>,
+}
+
+impl<K> fmt::Debug for GroupSet<K> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("GroupSet")
+			.field("len", &self.len())
+			.finish_non_exhaustive()
+	}
+}
+
+impl<K> Default for GroupSet<K> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<K> GroupSet<K> {
+	/// Creates an empty set.
+	pub fn new() -> Self {
+		Self {
+			waits: JoinSet::new(),
+		}
+	}
+
+	/// Returns the number of groups in the set, which haven't been returned as exited yet.
+	pub fn len(&self) -> usize {
+		self.waits.len()
+	}
+
+	/// Returns whether there are no groups left in the set.
+	pub fn is_empty(&self) -> bool {
+		self.waits.is_empty()
+	}
+}
+
+impl<K: Send + 'static> GroupSet<K> {
+	/// Adds a group to the set, under the given key.
+	///
+	/// Keys don't need to be unique, but then they won't tell the groups apart.
+	///
+	/// # Panics
+	///
+	/// If called outside of a Tokio runtime, as the group is waited on by a task spawned on it.
+	pub fn insert(&mut self, key: K, mut child: AsyncGroupChild) {
+		self.waits.spawn(async move {
+			let res = child.wait().await;
+			(key, res)
+		});
+	}
+
+	/// Waits for any group in the set to exit, and removes it from the set.
+	///
+	/// Returns the group's key, with the result of [waiting](AsyncGroupChild::wait) on it, or
+	/// `None` if the set is empty. A group is removed from the set even if waiting on it failed.
+	///
+	/// # Cancel safety
+	///
+	/// This method is cancel safe: if the future is dropped before it completes, every group stays
+	/// in the set, and any status already collected is returned by a later call.
+	pub async fn next_exited(&mut self) -> Option<(K, Result<ExitStatus>)> {
+		match self.waits.join_next().await? {
+			Ok(exited) => Some(exited),
+			// tasks are only aborted when the set is dropped, so this can only be a panic
+			Err(err) => resume_unwind(err.into_panic()),
+		}
+	}
+}
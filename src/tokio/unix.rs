@@ -1,4 +1,4 @@
-use crate::builder::CommandGroupBuilder;
+use crate::builder::{ChildSetup, CommandGroupBuilder};
 use crate::AsyncGroupChild;
 
 impl CommandGroupBuilder<'_, tokio::process::Command> {
@@ -21,23 +21,45 @@ impl CommandGroupBuilder<'_, tokio::process::Command> {
 	///         .spawn()
 	///         .expect("ls command failed to start");
 	/// ```
-	pub fn spawn(&mut self) -> std::io::Result<AsyncGroupChild> {
+	pub fn spawn(&mut self) -> crate::Result<AsyncGroupChild> {
+		self.spawn_imp().map_err(crate::Error::spawn)
+	}
+
+	fn spawn_imp(&mut self) -> std::io::Result<AsyncGroupChild> {
+		let process_group = if self.new_session {
+			// setsid() also creates a new process group, led by the child
+			0
+		} else {
+			self.process_group
+		};
+
+		// with tokio_unstable, the command can join the group itself, as std's does
 		#[cfg(tokio_unstable)]
-		{
-			self.command.process_group(0);
-		}
+		let join_group = {
+			if !self.new_session {
+				self.command.process_group(process_group);
+				self.sets_process_group = true;
+			} else if self.sets_process_group {
+				// as with std's, leave the child in our group so that it can call setsid()
+				self.command.process_group(nix::unistd::getpgrp().as_raw());
+			}
+			None
+		};
 
 		#[cfg(not(tokio_unstable))]
-		unsafe {
-			use nix::unistd::{setpgid, Pid};
-			use std::io::Error;
-			self.command.pre_exec(|| {
-				setpgid(Pid::this(), Pid::from_raw(0))
-					.map_err(Error::from)
-					.map(|_| ())
-			});
+		let join_group = (!self.new_session).then_some(process_group);
+
+		let setup = self.child_setup(join_group)?;
+		if setup.is_some() && self.register_setup_hook() {
+			unsafe {
+				self.command.pre_exec(ChildSetup::run_current);
+			}
 		}
 
-		self.command.spawn().map(AsyncGroupChild::new)
+		let kill_on_drop = self.kill_on_drop.then_some(self.kill_on_drop_signal);
+		let _current = setup.as_ref().map(ChildSetup::make_current);
+		self.command
+			.spawn()
+			.map(|child| AsyncGroupChild::new(child, process_group, kill_on_drop))
 	}
 }
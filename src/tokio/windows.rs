@@ -1,5 +1,6 @@
-use tokio::process::Command;
-use winapi::um::winbase::CREATE_SUSPENDED;
+use std::process::Stdio;
+use tokio::process::{ChildStdout, Command};
+use winapi::um::winbase::{CREATE_NEW_PROCESS_GROUP, CREATE_SUSPENDED};
 
 use crate::{builder::CommandGroupBuilder, winres::*, AsyncGroupChild};
 
@@ -20,22 +21,56 @@ impl CommandGroupBuilder<'_, Command> {
 	///
 	/// Command::new("ls")
 	///         .group()
-	/// 		.spawn()
+	///         .spawn()
 	///         .expect("ls command failed to start");
 	/// ```
-	pub fn spawn(&mut self) -> std::io::Result<AsyncGroupChild> {
-		let (job, completion_port) = job_object(self.kill_on_drop)?;
+	pub fn spawn(&mut self) -> crate::Result<AsyncGroupChild> {
+		self.spawn_imp().map_err(crate::Error::spawn)
+	}
+
+	fn spawn_imp(&mut self) -> std::io::Result<AsyncGroupChild> {
+		let handles = job_object(self.kill_on_drop, &self.job_limits)?;
+		let creation_flags = self.windows_creation_flags();
+		// the child is started suspended so it can't escape the job before it's assigned to it,
+		// but if it was asked to start suspended, it stays that way
+		let resume = creation_flags & CREATE_SUSPENDED == 0;
 		self.command
-			.creation_flags(self.creation_flags | CREATE_SUSPENDED);
+			.creation_flags(creation_flags | CREATE_SUSPENDED);
+
+		let merged = if self.merge_stderr {
+			let (read, write) = pipe()?;
+			self.command.stdout(write.try_clone()?).stderr(write);
+			Some(read)
+		} else {
+			None
+		};
+
+		let child = self.command.spawn();
+		// don't leave our flag behind, in case the command is spawned again some other way
+		self.command.creation_flags(creation_flags);
+		if merged.is_some() {
+			// the command holds on to the pipe's write end, which would keep it from ever closing
+			self.command
+				.stdout(Stdio::inherit())
+				.stderr(Stdio::inherit());
+		}
 
-		let child = self.command.spawn()?;
+		let mut child = child?;
 		assign_child(
 			child
 				.raw_handle()
 				.expect("child has exited but it has not even started"),
-			job,
+			handles.raw_job(),
+			resume,
 		)?;
+		if let Some(read) = merged {
+			child.stdout = Some(ChildStdout::from_std(read.into())?);
+		}
 
-		Ok(AsyncGroupChild::new(child, job, completion_port))
+		Ok(AsyncGroupChild::new(
+			child,
+			handles,
+			creation_flags & CREATE_NEW_PROCESS_GROUP != 0,
+		))
 	}
 }
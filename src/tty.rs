@@ -0,0 +1,49 @@
+use std::io::{Error, Result};
+
+use nix::libc;
+
+/// Makes a process group the foreground group of our controlling terminal.
+///
+/// Changing the foreground group from a background group raises `SIGTTOU`, which would stop us,
+/// so it's blocked for the duration of the call. This only calls async-signal-safe functions,
+/// so it can also run in the child, between fork and exec.
+pub(crate) fn set_foreground(pgid: libc::pid_t) -> Result<()> {
+	unsafe {
+		let mut ttou = std::mem::MaybeUninit::<libc::sigset_t>::uninit();
+		let mut previous = std::mem::MaybeUninit::<libc::sigset_t>::uninit();
+		libc::sigemptyset(ttou.as_mut_ptr());
+		libc::sigaddset(ttou.as_mut_ptr(), libc::SIGTTOU);
+		libc::pthread_sigmask(libc::SIG_BLOCK, ttou.as_ptr(), previous.as_mut_ptr());
+
+		let res = foreground_imp(pgid);
+
+		libc::pthread_sigmask(libc::SIG_SETMASK, previous.as_ptr(), std::ptr::null_mut());
+		res
+	}
+}
+
+unsafe fn foreground_imp(pgid: libc::pid_t) -> Result<()> {
+	// /dev/tty is always the controlling terminal, whichever of our stdio may be redirected
+	let tty = libc::open(
+		b"/dev/tty\0".as_ptr().cast(),
+		libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC,
+	);
+	if tty == -1 {
+		return Err(Error::last_os_error());
+	}
+
+	let res = if libc::tcsetpgrp(tty, pgid) == -1 {
+		Err(Error::last_os_error())
+	} else {
+		Ok(())
+	};
+	libc::close(tty);
+	res
+}
+
+/// Makes the calling process's group the foreground group of its controlling terminal.
+///
+/// This runs in the child, between fork and exec, after it has joined its process group.
+pub(crate) fn foreground_child() -> Result<()> {
+	set_foreground(unsafe { libc::getpgrp() })
+}
@@ -1,9 +1,6 @@
-use std::{
-	convert::TryInto,
-	io::{Error, Result},
-	process::Child,
-};
+use std::{convert::TryInto, process::Child};
 
+use crate::{error::kill_error, Error, Result};
 use nix::{
 	sys::signal::{kill, Signal},
 	unistd::Pid,
@@ -11,8 +8,11 @@ use nix::{
 
 /// Unix-specific extensions to process [`Child`]ren.
 pub trait UnixChildExt {
-	/// Sends a signal to the child process. If the process has already exited, an [`InvalidInput`]
-	/// error is returned.
+	/// Sends a signal to the child process. If the process has already exited and been reaped,
+	/// [`Error::AlreadyExited`] is returned.
+	///
+	/// For this crate's group children, the signal is sent to every process in the group. Use
+	/// their `signal_leader()` method to only signal the group leader.
 	///
 	/// # Examples
 	///
@@ -43,15 +43,13 @@ pub trait UnixChildExt {
 	///     println!("yes command didn't start");
 	/// }
 	/// ```
-	///
-	/// [`InvalidInput`]: std::io::ErrorKind::InvalidInput
 	fn signal(&self, sig: Signal) -> Result<()>;
 }
 
 impl UnixChildExt for Child {
 	fn signal(&self, sig: Signal) -> Result<()> {
 		let pid = Pid::from_raw(self.id().try_into().expect("Command PID > i32::MAX"));
-		kill(pid, sig).map_err(Error::from)
+		kill(pid, sig).map_err(|err| Error::signal(kill_error(err)))
 	}
 }
 
@@ -60,9 +58,10 @@ impl UnixChildExt for ::tokio::process::Child {
 	fn signal(&self, sig: Signal) -> Result<()> {
 		if let Some(id) = self.id() {
 			let pid = Pid::from_raw(id.try_into().expect("Command PID > i32::MAX"));
-			kill(pid, sig).map_err(Error::from)
+			kill(pid, sig).map_err(|err| Error::signal(kill_error(err)))
 		} else {
-			Ok(())
+			// the child has been reaped, so its pid may have been reused
+			Err(Error::AlreadyExited)
 		}
 	}
 }
@@ -1,50 +1,63 @@
 use std::{
 	convert::TryInto,
-	io::{Error, Result},
+	io::{Error, ErrorKind, Result},
 	mem,
-	os::windows::io::RawHandle,
-	ptr,
+	os::windows::io::{AsRawHandle, FromRawHandle, HandleOrInvalid, OwnedHandle, RawHandle},
+	ptr, slice,
+	sync::OnceLock,
+	time::{Duration, Instant},
 };
 use winapi::{
-	shared::minwindef::{BOOL, DWORD, FALSE, LPVOID},
+	shared::{
+		basetsd::ULONG_PTR,
+		minwindef::{BOOL, DWORD, FALSE, FARPROC, LPVOID},
+		ntdef::NTSTATUS,
+		winerror::ERROR_MORE_DATA,
+	},
 	um::{
-		handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
-		ioapiset::CreateIoCompletionPort,
-		jobapi2::{AssignProcessToJobObject, CreateJobObjectW, SetInformationJobObject},
-		processthreadsapi::{GetProcessId, OpenThread, ResumeThread},
+		handleapi::INVALID_HANDLE_VALUE,
+		ioapiset::{CreateIoCompletionPort, GetQueuedCompletionStatus},
+		jobapi2::{
+			AssignProcessToJobObject, CreateJobObjectW, QueryInformationJobObject,
+			SetInformationJobObject, TerminateJobObject,
+		},
+		libloaderapi::{GetModuleHandleW, GetProcAddress},
+		namedpipeapi::CreatePipe,
+		processthreadsapi::{GetProcessId, OpenThread, ResumeThread, TerminateProcess},
 		tlhelp32::{
 			CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32,
 		},
+		winbase::INFINITE,
+		wincon::{GenerateConsoleCtrlEvent, CTRL_BREAK_EVENT, CTRL_C_EVENT},
 		winnt::{
-			JobObjectAssociateCompletionPortInformation, JobObjectExtendedLimitInformation, HANDLE,
-			JOBOBJECT_ASSOCIATE_COMPLETION_PORT, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
-			JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+			JobObjectAssociateCompletionPortInformation, JobObjectBasicAccountingInformation,
+			JobObjectBasicProcessIdList, JobObjectExtendedLimitInformation, HANDLE,
+			JOBOBJECT_ASSOCIATE_COMPLETION_PORT, JOBOBJECT_BASIC_ACCOUNTING_INFORMATION,
+			JOBOBJECT_BASIC_PROCESS_ID_LIST, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+			JOB_OBJECT_LIMIT_ACTIVE_PROCESS, JOB_OBJECT_LIMIT_JOB_MEMORY,
+			JOB_OBJECT_LIMIT_JOB_TIME, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+			JOB_OBJECT_LIMIT_PROCESS_MEMORY, JOB_OBJECT_MSG_ACTIVE_PROCESS_ZERO, LARGE_INTEGER,
 		},
 	},
 };
 
-#[derive(Clone)]
+/// The job object of a group, and the completion port that receives its notifications.
+///
+/// Both handles are closed on drop.
 pub(crate) struct JobPort {
-	pub job: HANDLE,
-	pub completion_port: HANDLE,
+	pub job: OwnedHandle,
+	pub completion_port: OwnedHandle,
 }
 
-impl Drop for JobPort {
-	fn drop(&mut self) {
-		unsafe { CloseHandle(self.job) };
-		unsafe { CloseHandle(self.completion_port) };
+impl JobPort {
+	pub fn raw_job(&self) -> HANDLE {
+		self.job.as_raw_handle() as _
 	}
-}
-
-unsafe impl Send for JobPort {}
-unsafe impl Sync for JobPort {}
 
-#[derive(Copy, Clone)]
-#[repr(transparent)]
-pub(crate) struct ThreadSafeRawHandle(pub HANDLE);
-
-unsafe impl Send for ThreadSafeRawHandle {}
-unsafe impl Sync for ThreadSafeRawHandle {}
+	pub fn raw_completion_port(&self) -> HANDLE {
+		self.completion_port.as_raw_handle() as _
+	}
+}
 
 pub(crate) fn res_null(handle: HANDLE) -> Result<HANDLE> {
 	if handle.is_null() {
@@ -70,20 +83,99 @@ pub(crate) fn res_neg(ret: DWORD) -> Result<DWORD> {
 	}
 }
 
-pub(crate) fn job_object(kill_on_drop: bool) -> Result<(HANDLE, HANDLE)> {
+/// Maps a signal to what a lone (ungrouped) process supports: only forcibly terminating it.
+pub(crate) fn signal_process(process: RawHandle, sig: crate::Signal) -> Result<()> {
+	match sig {
+		crate::Signal::Kill | crate::Signal::Terminate => {
+			res_bool(unsafe { TerminateProcess(process as HANDLE, 1) })
+		}
+		_ => Err(unsupported_signal(sig)),
+	}
+}
+
+pub(crate) fn unsupported_signal(sig: crate::Signal) -> Error {
+	Error::new(
+		ErrorKind::Unsupported,
+		format!("{sig:?} can't be sent on Windows"),
+	)
+}
+
+/// Sends a console control event to the group, if it still has processes in it.
+///
+/// The group is only addressable as a console process group when the leader was created with
+/// `CREATE_NEW_PROCESS_GROUP`: the console process group ID is then the leader's process ID.
+pub(crate) fn send_control(
+	job: HANDLE,
+	leader: Option<u32>,
+	new_process_group: bool,
+	event: crate::ControlEvent,
+) -> Result<()> {
+	// Sending an event to any other id would broadcast it to every process attached to our
+	// console, including ourselves.
+	if !new_process_group {
+		return Err(Error::new(
+			ErrorKind::Unsupported,
+			"the group was not created with CREATE_NEW_PROCESS_GROUP",
+		));
+	}
+
+	let active = job_active_processes(job)?;
+	let Some(leader) = leader.filter(|_| active > 0) else {
+		return Err(crate::Error::AlreadyExited.into());
+	};
+
+	let event = match event {
+		crate::ControlEvent::CtrlC => CTRL_C_EVENT,
+		crate::ControlEvent::CtrlBreak => CTRL_BREAK_EVENT,
+	};
+	res_bool(unsafe { GenerateConsoleCtrlEvent(event, leader) })
+}
+
+/// Resource limits applied to a job object when it's created.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct JobLimits {
+	pub max_processes: Option<u32>,
+	pub memory: Option<usize>,
+	pub process_memory: Option<usize>,
+	pub cpu_time: Option<Duration>,
+}
+
+/// Creates an anonymous pipe, returning its read and write ends.
+pub(crate) fn pipe() -> Result<(OwnedHandle, OwnedHandle)> {
+	let (mut read, mut write) = (ptr::null_mut(), ptr::null_mut());
+	res_bool(unsafe { CreatePipe(&mut read, &mut write, ptr::null_mut(), 0) })?;
+
+	// SAFETY: the call succeeded, so these are new handles that nothing else owns
+	Ok(unsafe {
+		(
+			OwnedHandle::from_raw_handle(read as _),
+			OwnedHandle::from_raw_handle(write as _),
+		)
+	})
+}
+
+pub(crate) fn job_object(kill_on_drop: bool, limits: &JobLimits) -> Result<JobPort> {
+	// take ownership of each handle as soon as it's created, so they're closed on error
 	let job = res_null(unsafe { CreateJobObjectW(ptr::null_mut(), ptr::null()) })?;
+	let job = unsafe { OwnedHandle::from_raw_handle(job as _) };
 
 	let completion_port =
 		res_null(unsafe { CreateIoCompletionPort(INVALID_HANDLE_VALUE, ptr::null_mut(), 0, 1) })?;
+	let completion_port = unsafe { OwnedHandle::from_raw_handle(completion_port as _) };
+
+	let handles = JobPort {
+		job,
+		completion_port,
+	};
 
 	let mut associate_completion = JOBOBJECT_ASSOCIATE_COMPLETION_PORT {
-		CompletionKey: job,
-		CompletionPort: completion_port,
+		CompletionKey: handles.raw_job(),
+		CompletionPort: handles.raw_completion_port(),
 	};
 
 	res_bool(unsafe {
 		SetInformationJobObject(
-			job,
+			handles.raw_job(),
 			JobObjectAssociateCompletionPortInformation,
 			&mut associate_completion as *mut _ as LPVOID,
 			mem::size_of_val(&associate_completion)
@@ -95,12 +187,39 @@ pub(crate) fn job_object(kill_on_drop: bool) -> Result<(HANDLE, HANDLE)> {
 	let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
 
 	if kill_on_drop {
-		info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+		info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+	}
+
+	if let Some(max) = limits.max_processes {
+		info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_ACTIVE_PROCESS;
+		info.BasicLimitInformation.ActiveProcessLimit = max;
+	}
+
+	if let Some(memory) = limits.memory {
+		info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY;
+		info.JobMemoryLimit = memory;
+	}
+
+	if let Some(memory) = limits.process_memory {
+		info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+		info.ProcessMemoryLimit = memory;
+	}
+
+	if let Some(time) = limits.cpu_time {
+		info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_JOB_TIME;
+		// counted in 100-nanosecond ticks
+		let ticks = (time.as_nanos() / 100).try_into().unwrap_or(i64::MAX);
+		unsafe {
+			*info
+				.BasicLimitInformation
+				.PerJobUserTimeLimit
+				.QuadPart_mut() = ticks
+		};
 	}
 
 	res_bool(unsafe {
 		SetInformationJobObject(
-			job,
+			handles.raw_job(),
 			JobObjectExtendedLimitInformation,
 			&mut info as *mut _ as LPVOID,
 			mem::size_of_val(&info)
@@ -109,15 +228,233 @@ pub(crate) fn job_object(kill_on_drop: bool) -> Result<(HANDLE, HANDLE)> {
 		)
 	})?;
 
-	Ok((job, completion_port))
+	Ok(handles)
+}
+
+pub(crate) fn job_accounting(job: HANDLE) -> Result<crate::JobAccounting> {
+	let mut basic = JOBOBJECT_BASIC_ACCOUNTING_INFORMATION::default();
+	query_job(job, JobObjectBasicAccountingInformation, &mut basic)?;
+
+	let mut extended = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+	query_job(job, JobObjectExtendedLimitInformation, &mut extended)?;
+
+	// these times are counted in 100-nanosecond ticks
+	fn ticks(time: LARGE_INTEGER) -> Duration {
+		let ticks = unsafe { *time.QuadPart() };
+		Duration::from_nanos(u64::try_from(ticks).unwrap_or(0).saturating_mul(100))
+	}
+
+	Ok(crate::JobAccounting {
+		user_time: ticks(basic.TotalUserTime),
+		kernel_time: ticks(basic.TotalKernelTime),
+		peak_memory: extended.PeakJobMemoryUsed,
+		total_processes: basic.TotalProcesses,
+	})
+}
+
+/// Clears the job's `JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE` limit, so closing it leaves its processes
+/// running.
+pub(crate) fn disarm_kill_on_close(job: HANDLE) -> Result<()> {
+	let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+	query_job(job, JobObjectExtendedLimitInformation, &mut info)?;
+	if info.BasicLimitInformation.LimitFlags & JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE == 0 {
+		return Ok(());
+	}
+
+	info.BasicLimitInformation.LimitFlags &= !JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+	res_bool(unsafe {
+		SetInformationJobObject(
+			job,
+			JobObjectExtendedLimitInformation,
+			&mut info as *mut _ as LPVOID,
+			mem::size_of_val(&info)
+				.try_into()
+				.expect("cannot safely cast to DWORD"),
+		)
+	})
+}
+
+/// Terminates every process in the job, with the given exit code.
+///
+/// Terminating a job which has no processes left succeeds without doing anything, so that's
+/// checked first, to report that the group has already exited like killing it does on Unix.
+pub(crate) fn terminate_job(job: HANDLE, code: u32) -> Result<()> {
+	if job_active_processes(job)? == 0 {
+		return Err(crate::Error::AlreadyExited.into());
+	}
+
+	res_bool(unsafe { TerminateJobObject(job, code) })
+}
+
+pub(crate) fn job_active_processes(job: HANDLE) -> Result<u32> {
+	let mut basic = JOBOBJECT_BASIC_ACCOUNTING_INFORMATION::default();
+	query_job(job, JobObjectBasicAccountingInformation, &mut basic)?;
+	Ok(basic.ActiveProcesses)
+}
+
+/// Waits up to `timeout` milliseconds for no process to be left in the job, returning whether
+/// that happened.
+///
+/// Only the job's own `JOB_OBJECT_MSG_ACTIVE_PROCESS_ZERO` notification ends the wait early:
+/// others, like those for processes starting or exiting in the job, are skipped. With a timeout of
+/// zero, this drains all the notifications already queued, so a single call is enough to see that
+/// one, however many came before it. It is only sent once, so the job's process count is checked
+/// first, in case it was received before.
+pub(crate) fn wait_job_empty(handles: &JobPort, timeout: DWORD) -> Result<bool> {
+	let deadline = Instant::now() + Duration::from_millis(timeout.into());
+	loop {
+		if job_active_processes(handles.raw_job())? == 0 {
+			return Ok(true);
+		}
+
+		let remaining = if timeout == INFINITE {
+			INFINITE
+		} else {
+			// at most `timeout`, so this fits
+			deadline
+				.saturating_duration_since(Instant::now())
+				.as_millis() as DWORD
+		};
+
+		let mut code: DWORD = 0;
+		let mut key: ULONG_PTR = 0;
+		let mut overlapped = ptr::null_mut();
+		let result = unsafe {
+			GetQueuedCompletionStatus(
+				handles.raw_completion_port(),
+				&mut code,
+				&mut key,
+				&mut overlapped,
+				remaining,
+			)
+		};
+
+		// timing out is only an error when the timeout was INFINITE
+		// https://docs.microsoft.com/en-us/windows/win32/api/ioapiset/nf-ioapiset-getqueuedcompletionstatus
+		if remaining != INFINITE && result == FALSE && overlapped.is_null() {
+			return Ok(false);
+		}
+
+		res_bool(result)?;
+		if key == handles.raw_job() as ULONG_PTR && code == JOB_OBJECT_MSG_ACTIVE_PROCESS_ZERO {
+			return Ok(true);
+		}
+	}
+}
+
+pub(crate) fn job_pids(job: HANDLE) -> Result<Vec<u32>> {
+	const HEADER: usize =
+		mem::size_of::<JOBOBJECT_BASIC_PROCESS_ID_LIST>() / mem::size_of::<ULONG_PTR>();
+
+	let mut capacity = 16;
+	loop {
+		// a ULONG_PTR buffer is aligned for the list, which is followed by as many IDs as fit
+		let mut buf: Vec<ULONG_PTR> = vec![0; HEADER + capacity];
+		let ret = unsafe {
+			QueryInformationJobObject(
+				job,
+				JobObjectBasicProcessIdList,
+				buf.as_mut_ptr().cast(),
+				mem::size_of_val(buf.as_slice())
+					.try_into()
+					.expect("cannot safely cast to DWORD"),
+				ptr::null_mut(),
+			)
+		};
+		if ret == FALSE {
+			let err = Error::last_os_error();
+			if err.raw_os_error() != Some(ERROR_MORE_DATA as _) {
+				return Err(err);
+			}
+		}
+
+		let list = buf.as_ptr().cast::<JOBOBJECT_BASIC_PROCESS_ID_LIST>();
+		let (assigned, listed) = unsafe {
+			(
+				(*list).NumberOfAssignedProcesses as usize,
+				(*list).NumberOfProcessIdsInList as usize,
+			)
+		};
+
+		// processes may have been added since we last asked, so retry with room to spare
+		if listed < assigned {
+			capacity = assigned + 16;
+			continue;
+		}
+
+		let ids = unsafe { ptr::addr_of!((*list).ProcessIdList).cast::<ULONG_PTR>() };
+		let ids = unsafe { slice::from_raw_parts(ids, listed) };
+		return Ok(ids.iter().map(|&id| id as u32).collect());
+	}
+}
+
+fn query_job<T>(job: HANDLE, class: u32, info: &mut T) -> Result<()> {
+	res_bool(unsafe {
+		QueryInformationJobObject(
+			job,
+			class,
+			info as *mut _ as LPVOID,
+			mem::size_of_val(info)
+				.try_into()
+				.expect("cannot safely cast to DWORD"),
+			ptr::null_mut(),
+		)
+	})
+}
+
+type NtResumeProcess = unsafe extern "system" fn(HANDLE) -> NTSTATUS;
+
+/// Looks up the undocumented `NtResumeProcess` in ntdll, once.
+fn nt_resume_process() -> Option<NtResumeProcess> {
+	static RESUME: OnceLock<Option<NtResumeProcess>> = OnceLock::new();
+	*RESUME.get_or_init(|| {
+		let ntdll: Vec<u16> = "ntdll.dll\0".encode_utf16().collect();
+		let module = unsafe { GetModuleHandleW(ntdll.as_ptr()) };
+		if module.is_null() {
+			return None;
+		}
+
+		let func = unsafe { GetProcAddress(module, b"NtResumeProcess\0".as_ptr().cast()) };
+		if func.is_null() {
+			None
+		} else {
+			// SAFETY: this is NtResumeProcess's signature, as used by ntdll's own headers
+			Some(unsafe { mem::transmute::<FARPROC, NtResumeProcess>(func) })
+		}
+	})
+}
+
+/// Resumes every thread of a suspended process.
+///
+/// `NtResumeProcess` does so in one go. It's not part of the public Windows API, so if it's
+/// missing or fails, fall back to resuming each thread we can find.
+fn resume_process(child_process: HANDLE) -> Result<()> {
+	if let Some(resume) = nt_resume_process() {
+		if unsafe { resume(child_process) } >= 0 {
+			return Ok(());
+		}
+	}
+
+	resume_threads(child_process)
 }
 
 // This is pretty terrible, but it's either this or we re-implement all of Rust's std::process just
-// to get at PROCESS_INFORMATION!
+// to get at PROCESS_INFORMATION! It's also racy, as threads created after the snapshot is taken
+// are missed, but the process is suspended so it shouldn't be creating any.
+//
+// Creating the process directly in the job, with PROC_THREAD_ATTRIBUTE_JOB_LIST, would avoid the
+// suspend/resume dance and this race altogether. However, std's Command can only set process
+// attributes with the unstable `raw_attribute()`, and a Child can't be built from a process
+// handle, so that would also mean re-implementing std::process. Until then, this is a known gap.
 fn resume_threads(child_process: HANDLE) -> Result<()> {
 	let child_id = unsafe { GetProcessId(child_process) };
 
-	let h = res_null(unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) })?;
+	// unlike most handle-returning functions, this one signals failure with INVALID_HANDLE_VALUE
+	let snapshot = unsafe {
+		HandleOrInvalid::from_raw_handle(CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) as _)
+	};
+	let snapshot = OwnedHandle::try_from(snapshot).map_err(|_| Error::last_os_error())?;
+	let h = snapshot.as_raw_handle() as HANDLE;
 	let mut entry = THREADENTRY32 {
 		dwSize: 28,
 		cntUsage: 0,
@@ -131,20 +468,23 @@ fn resume_threads(child_process: HANDLE) -> Result<()> {
 	let mut res = res_bool(unsafe { Thread32First(h, &mut entry) });
 	while res.is_ok() {
 		if entry.th32OwnerProcessID == child_id {
-			let thread_handle = res_null(unsafe { OpenThread(0x0002, 0, entry.th32ThreadID) })?;
-			res_neg(unsafe { ResumeThread(thread_handle) })?;
-			res_bool(unsafe { CloseHandle(thread_handle) })?;
+			let thread = res_null(unsafe { OpenThread(0x0002, 0, entry.th32ThreadID) })?;
+			let thread = unsafe { OwnedHandle::from_raw_handle(thread as _) };
+			res_neg(unsafe { ResumeThread(thread.as_raw_handle() as _) })?;
 		}
 
 		res = res_bool(unsafe { Thread32Next(h, &mut entry) });
 	}
 
-	res_bool(unsafe { CloseHandle(h) })
+	Ok(())
 }
 
-pub(crate) fn assign_child(handle: RawHandle, job: HANDLE) -> Result<()> {
+/// Assigns a suspended child to the job, then resumes it unless it should stay suspended.
+pub(crate) fn assign_child(handle: RawHandle, job: HANDLE, resume: bool) -> Result<()> {
 	let handle = handle as _;
 	res_bool(unsafe { AssignProcessToJobObject(job, handle) })?;
-	resume_threads(handle)?;
+	if resume {
+		resume_process(handle)?;
+	}
 	Ok(())
 }
@@ -0,0 +1,171 @@
+#![cfg(all(unix, feature = "with-async-io"))]
+
+use async_io::{block_on, Timer};
+use command_group::{AsyncIoCommandGroup, CommandGroup, Signal, UnixChildExt};
+use nix::{
+	errno::Errno,
+	sys::{
+		signal::killpg,
+		wait::{waitpid, WaitStatus},
+	},
+	unistd::Pid,
+};
+use std::{
+	io::Result,
+	os::unix::process::ExitStatusExt,
+	process::{Command, Stdio},
+	time::Duration,
+};
+
+const DIE_TIME: Duration = Duration::from_millis(100);
+
+// there's no non-group async-io API to compare with, so these only have _group variants.
+
+#[test]
+fn wait_group() -> Result<()> {
+	block_on(async {
+		let mut child = Command::new("echo")
+			.stdout(Stdio::null())
+			.group_spawn_async()?;
+		let status = child.wait().await?;
+		assert!(status.success());
+		let status = child.wait().await?;
+		assert!(status.success());
+		Ok(())
+	})
+}
+
+#[test]
+fn group_status_group() -> Result<()> {
+	block_on(async {
+		let status = Command::new("sh")
+			.arg("-c")
+			.arg("exit 3")
+			.group_status_async()
+			.await?;
+		assert_eq!(status.code(), Some(3));
+		Ok(())
+	})
+}
+
+#[test]
+fn wait_closes_stdin_group() -> Result<()> {
+	block_on(async {
+		let mut child = Command::new("cat")
+			.stdin(Stdio::piped())
+			.stdout(Stdio::null())
+			.group_spawn_async()?;
+		let status = child.wait().await?;
+		assert!(status.success());
+		Ok(())
+	})
+}
+
+#[test]
+fn try_wait_group() -> Result<()> {
+	block_on(async {
+		let mut child = Command::new("yes")
+			.stdout(Stdio::null())
+			.group_spawn_async()?;
+		assert!(child.try_wait()?.is_none(), "pre kill");
+
+		child.start_kill()?;
+		Timer::after(DIE_TIME).await;
+		let status = child.try_wait()?.expect("killed");
+		assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+		Ok(())
+	})
+}
+
+#[test]
+fn id_checked_group() -> Result<()> {
+	block_on(async {
+		let mut child = Command::new("echo")
+			.stdout(Stdio::null())
+			.group_spawn_async()?;
+		assert_eq!(child.id_checked(), Some(child.id()));
+		child.wait().await?;
+		assert_eq!(child.id_checked(), None, "reaped");
+		Ok(())
+	})
+}
+
+#[test]
+fn kill_group() -> Result<()> {
+	block_on(async {
+		let mut child = Command::new("yes")
+			.stdout(Stdio::null())
+			.group_spawn_async()?;
+		child.kill().await?;
+
+		let err = child.start_kill().expect_err("group already exited");
+		assert!(matches!(err, command_group::Error::AlreadyExited));
+		Ok(())
+	})
+}
+
+#[test]
+fn wait_or_kill_finished_group() -> Result<()> {
+	block_on(async {
+		let mut child = Command::new("sh")
+			.arg("-c")
+			.arg("exit 3")
+			.group_spawn_async()?;
+		let status = child.wait_or_kill(DIE_TIME * 10).await?;
+		assert_eq!(status.and_then(|s| s.code()), Some(3));
+		Ok(())
+	})
+}
+
+#[test]
+fn wait_or_kill_killed_group() -> Result<()> {
+	block_on(async {
+		let mut child = Command::new("yes")
+			.stdout(Stdio::null())
+			.group_spawn_async()?;
+		assert_eq!(child.wait_or_kill(DIE_TIME).await?, None);
+		assert_eq!(child.wait().await?.signal(), Some(Signal::SIGKILL as i32));
+		Ok(())
+	})
+}
+
+#[test]
+fn signal_group() -> Result<()> {
+	block_on(async {
+		let mut child = Command::new("yes")
+			.stdout(Stdio::null())
+			.group_spawn_async()?;
+
+		child.signal(Signal::SIGCONT)?;
+		Timer::after(DIE_TIME).await;
+		assert!(child.try_wait()?.is_none(), "not exited with sigcont");
+
+		child.signal(Signal::SIGTERM)?;
+		let status = child.wait().await?;
+		assert_eq!(status.signal(), Some(Signal::SIGTERM as i32));
+		Ok(())
+	})
+}
+
+#[test]
+fn builder_kill_on_drop_group() -> Result<()> {
+	let child = Command::new("yes")
+		.stdout(Stdio::null())
+		.group()
+		.kill_on_drop(true)
+		.spawn_async()?;
+	let pgid = Pid::from_raw(child.id() as _);
+	drop(child);
+
+	// dropping doesn't wait for the group to exit, so the leader may still need reaping
+	let reaped = waitpid(pgid, None);
+	assert!(
+		matches!(
+			reaped,
+			Ok(WaitStatus::Signaled(_, Signal::SIGKILL, _)) | Err(Errno::ECHILD)
+		),
+		"{reaped:?}"
+	);
+	assert_eq!(killpg(pgid, None), Err(Errno::ESRCH));
+	Ok(())
+}
@@ -0,0 +1,52 @@
+#![cfg(all(windows, feature = "with-async-io"))]
+
+use async_io::block_on;
+use command_group::AsyncIoCommandGroup;
+use std::{io::Result, process::Command, time::Duration};
+
+const DIE_TIME: Duration = Duration::from_millis(1000);
+
+// there's no non-group async-io API to compare with, so these only have _group variants.
+
+#[test]
+fn wait_group() -> Result<()> {
+	block_on(async {
+		let mut child = Command::new("powershell.exe")
+			.arg("/C")
+			.arg("echo hello")
+			.group_spawn_async()?;
+		let status = child.wait().await?;
+		assert!(status.success());
+		let status = child.wait().await?;
+		assert!(status.success());
+		Ok(())
+	})
+}
+
+#[test]
+fn kill_group() -> Result<()> {
+	block_on(async {
+		let mut child = Command::new("powershell.exe")
+			.arg("/C")
+			.arg("pause")
+			.group_spawn_async()?;
+		child.kill().await?;
+
+		let err = child.start_kill().expect_err("group already exited");
+		assert!(matches!(err, command_group::Error::AlreadyExited));
+		Ok(())
+	})
+}
+
+#[test]
+fn wait_or_kill_killed_group() -> Result<()> {
+	block_on(async {
+		let mut child = Command::new("powershell.exe")
+			.arg("/C")
+			.arg("pause")
+			.group_spawn_async()?;
+		assert_eq!(child.wait_or_kill(DIE_TIME).await?, None);
+		assert!(!child.wait().await?.success());
+		Ok(())
+	})
+}
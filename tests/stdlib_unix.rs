@@ -1,8 +1,19 @@
 #![cfg(unix)]
 
-use command_group::{CommandGroup, Signal, UnixChildExt};
+use command_group::{
+	builder::CommandGroupBuilder, stdlib::ErasedChild, CommandGroup, GroupChild, GroupStatus,
+	OutputLine, OutputSource, Resource, Signal, UnixChildExt,
+};
+use nix::{
+	errno::Errno,
+	sys::{
+		signal::killpg,
+		wait::{waitpid, WaitStatus},
+	},
+	unistd::{getpgid, getsid, Pid},
+};
 use std::{
-	io::{Read, Result, Write},
+	io::{ErrorKind, Read, Result, Write},
 	os::unix::process::ExitStatusExt,
 	process::{Command, Stdio},
 	thread::sleep,
@@ -45,6 +56,235 @@ fn inner_read_stdout_group() -> Result<()> {
 	Ok(())
 }
 
+#[test]
+fn read_stdout_group() -> Result<()> {
+	let mut child = Command::new("echo")
+		.arg("hello")
+		.stdout(Stdio::piped())
+		.group_spawn()?;
+
+	let mut output = String::new();
+	child.read_to_string(&mut output)?;
+	assert_eq!(output.as_str(), "hello\n");
+	assert!(child.wait()?.success());
+	Ok(())
+}
+
+#[test]
+fn read_unpiped_stdout_group() -> Result<()> {
+	let mut child = Command::new("true").group_spawn()?;
+	let err = child.read(&mut [0; 8]).expect_err("stdout isn't piped");
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn group_spawn_split_group() -> Result<()> {
+	let (mut child, stdio) = Command::new("cat")
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.group_spawn_split()?;
+	assert!(stdio.stderr.is_none(), "stderr wasn't piped");
+
+	let reader = std::thread::spawn(move || {
+		let mut output = String::new();
+		stdio.stdout.expect("piped").read_to_string(&mut output)?;
+		Ok::<_, std::io::Error>(output)
+	});
+	let mut stdin = stdio.stdin.expect("piped");
+	stdin.write_all(b"hello")?;
+	drop(stdin);
+
+	assert!(child.wait()?.success());
+	assert_eq!(reader.join().expect("reader panicked")?, "hello");
+	assert!(child.inner().stdout.is_none(), "taken");
+	Ok(())
+}
+
+#[test]
+fn group_output_string_group() -> Result<()> {
+	let (status, stdout, stderr) = Command::new("sh")
+		.arg("-c")
+		.arg("echo hello; printf 'bad \\377' >&2; exit 3")
+		.group_output_string()?;
+	assert_eq!(status.code(), Some(3));
+	assert_eq!(stdout, "hello\n");
+	assert_eq!(stderr, "bad \u{FFFD}", "decoded lossily");
+	Ok(())
+}
+
+#[test]
+fn builder_stdio_group() -> Result<()> {
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("cat; echo oops >&2")
+		.group()
+		.kill_on_drop(true)
+		.stdin(Stdio::null())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::null())
+		.spawn()?;
+
+	let output = child.wait_with_output()?;
+	assert!(output.status.success());
+	assert!(output.stdout.is_empty(), "stdin was null");
+	assert!(output.stderr.is_empty(), "stderr wasn't piped");
+	Ok(())
+}
+
+#[test]
+fn into_group_group() -> Result<()> {
+	fn configure() -> CommandGroupBuilder<'static, Command> {
+		let mut command = Command::new("sh");
+		command.arg("-c").arg("exit 3");
+		let mut builder = command.into_group();
+		builder.kill_on_drop(true);
+		builder
+	}
+
+	let mut builder = configure();
+	let mut child = builder.spawn()?;
+	assert_eq!(
+		getpgid(Some(Pid::from_raw(child.id() as _)))?.as_raw(),
+		child.id() as i32
+	);
+	assert_eq!(child.wait()?.code(), Some(3));
+	Ok(())
+}
+
+#[test]
+fn builder_command_mut_group() -> Result<()> {
+	let mut command = Command::new("sh");
+	command.arg("-c").arg("exit $CODE");
+
+	let mut builder = command.group();
+	builder.kill_on_drop(true);
+	builder.get_command_mut().env("CODE", "3");
+	assert_eq!(builder.get_command().get_program(), "sh");
+
+	let status = builder.spawn()?.wait()?;
+	assert_eq!(status.code(), Some(3));
+	Ok(())
+}
+
+#[test]
+fn builder_rlimit_group() -> Result<()> {
+	let mut command = Command::new("sh");
+	command.arg("-c").arg("ulimit -Sn; ulimit -Hn; ulimit -Sc");
+
+	let mut builder = command.group();
+	builder
+		.rlimit(Resource::RLIMIT_NOFILE, 32, 256)
+		.rlimit(Resource::RLIMIT_CORE, 0, 0)
+		.rlimit(Resource::RLIMIT_NOFILE, 64, 128)
+		.stdout(Stdio::piped());
+
+	let output = builder.spawn()?.wait_with_output()?;
+	assert!(output.status.success());
+	assert_eq!(
+		output.stdout, b"64\n128\n0\n",
+		"last limit for each resource"
+	);
+
+	let output = builder.spawn()?.wait_with_output()?;
+	assert_eq!(output.stdout, b"64\n128\n0\n", "spawned again");
+	Ok(())
+}
+
+#[test]
+fn builder_rlimit_denied_group() -> Result<()> {
+	let err = Command::new("true")
+		.group()
+		.rlimit(Resource::RLIMIT_NOFILE, 128, 64)
+		.spawn()
+		.expect_err("soft limit above hard limit");
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	Ok(())
+}
+
+#[test]
+fn builder_nice_group() -> Result<()> {
+	let base = Command::new("nice").output()?.stdout;
+	let base: i32 = String::from_utf8_lossy(&base)
+		.trim()
+		.parse()
+		.expect("niceness");
+
+	let output = Command::new("nice")
+		.group()
+		.nice(5)
+		.stdout(Stdio::piped())
+		.spawn()?
+		.wait_with_output()?;
+	assert!(output.status.success());
+	let nice: i32 = String::from_utf8_lossy(&output.stdout)
+		.trim()
+		.parse()
+		.expect("niceness");
+	assert_eq!(nice, (base + 5).min(19));
+	Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn builder_cgroup_group() -> Result<()> {
+	// our own cgroup in the unified hierarchy, and where that's mounted
+	let own = std::fs::read_to_string("/proc/self/cgroup")?;
+	let Some(own) = own.lines().find_map(|line| line.strip_prefix("0::")) else {
+		return Ok(());
+	};
+	let Some(root) = ["/sys/fs/cgroup", "/sys/fs/cgroup/unified"]
+		.into_iter()
+		.find(|root| {
+			std::path::Path::new(root)
+				.join("cgroup.controllers")
+				.exists()
+		})
+	else {
+		return Ok(());
+	};
+
+	let name = format!("command-group-test-{}", std::process::id());
+	let dir = std::path::Path::new(root)
+		.join(own.trim_start_matches('/'))
+		.join(&name);
+	if std::fs::create_dir(&dir).is_err() {
+		// cgroups aren't delegated to us
+		return Ok(());
+	}
+
+	let output = Command::new("cat")
+		.arg("/proc/self/cgroup")
+		.group()
+		.cgroup(&dir)
+		.stdout(Stdio::piped())
+		.spawn()
+		.and_then(|child| child.wait_with_output());
+	std::fs::remove_dir(&dir)?;
+
+	let output = String::from_utf8(output?.stdout).expect("utf-8");
+	assert!(
+		output
+			.lines()
+			.any(|line| line.starts_with("0::") && line.ends_with(&name)),
+		"in the cgroup: {output}"
+	);
+	Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn builder_cgroup_missing_group() -> Result<()> {
+	let err = Command::new("true")
+		.group()
+		.cgroup("/sys/fs/cgroup/command-group-test-missing")
+		.spawn()
+		.expect_err("no such cgroup");
+	assert_eq!(err.kind(), ErrorKind::NotFound);
+	Ok(())
+}
+
 #[test]
 fn into_inner_write_stdin_normal() -> Result<()> {
 	let mut child = Command::new("cat")
@@ -66,6 +306,7 @@ fn into_inner_write_stdin_normal() -> Result<()> {
 }
 
 #[test]
+#[allow(clippy::zombie_processes)]
 fn into_inner_write_stdin_group() -> Result<()> {
 	let mut child = Command::new("cat")
 		.stdin(Stdio::piped())
@@ -174,6 +415,822 @@ fn wait_twice_after_sigterm_group() -> Result<()> {
 	Ok(())
 }
 
+#[test]
+fn start_kill_and_wait_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	child.start_kill()?;
+	let status = child.wait()?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[test]
+fn terminate_and_wait_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	assert!(child.try_wait()?.is_none(), "pre try_wait");
+	child.terminate()?;
+	let status = child.wait()?;
+	assert_eq!(status.signal(), Some(Signal::SIGTERM as i32));
+	Ok(())
+}
+
+#[test]
+fn kill_with_and_wait_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	child.kill_with(Signal::SIGQUIT)?;
+	let status = child.wait()?;
+	assert_eq!(status.signal(), Some(Signal::SIGQUIT as i32));
+	Ok(())
+}
+
+#[test]
+fn spawn_error_group() -> Result<()> {
+	let err = Command::new("/nonexistent/command")
+		.group_spawn()
+		.expect_err("command doesn't exist");
+	assert_eq!(err.kind(), ErrorKind::NotFound);
+	assert!(matches!(err, command_group::Error::Spawn(_)));
+
+	// converting back gives the original error
+	let err = std::io::Error::from(err);
+	assert_eq!(err.raw_os_error(), Some(Errno::ENOENT as i32));
+	Ok(())
+}
+
+#[test]
+fn kill_after_wait_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	child.kill()?;
+	child.wait()?;
+
+	let err = child.kill().expect_err("group already exited");
+	assert!(matches!(err, command_group::Error::AlreadyExited));
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	Ok(())
+}
+
+#[test]
+fn signal_leader_group() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("trap 'exit 3' USR1; sleep 10 & wait")
+		.stdout(Stdio::null())
+		.group_spawn()?;
+	let pgid = Pid::from_raw(child.id() as _);
+	sleep(DIE_TIME);
+	child.signal_leader(Signal::SIGUSR1)?;
+	let status = child.wait()?;
+	assert_eq!(status.code(), Some(3));
+
+	// the rest of the group was left alone
+	assert_eq!(killpg(pgid, None), Ok(()));
+	killpg(pgid, Signal::SIGKILL)?;
+
+	assert!(
+		matches!(
+			child.signal_leader(Signal::SIGUSR1),
+			Err(command_group::Error::AlreadyExited)
+		),
+		"leader already reaped"
+	);
+	Ok(())
+}
+
+#[test]
+fn signal_leader_reaped_through_inner_group() -> Result<()> {
+	let mut child = Command::new("sh").arg("-c").arg("exit 3").group_spawn()?;
+	let status = child.inner().wait()?;
+	assert_eq!(status.code(), Some(3));
+
+	assert!(
+		matches!(
+			child.signal_leader(Signal::SIGUSR1),
+			Err(command_group::Error::AlreadyExited)
+		),
+		"leader reaped behind the wrapper's back"
+	);
+	assert_eq!(child.wait()?.code(), Some(3));
+	Ok(())
+}
+
+#[test]
+fn wait_timeout_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	assert!(child.wait_timeout(DIE_TIME)?.is_none(), "timed out");
+	child.kill()?;
+	let status = child.wait_timeout(DIE_TIME * 10)?;
+	assert_eq!(
+		status.and_then(|s| s.signal()),
+		Some(Signal::SIGKILL as i32)
+	);
+	assert_eq!(child.wait_timeout(DIE_TIME)?, status, "second wait_timeout");
+	assert_eq!(Some(child.wait()?), status, "wait after wait_timeout");
+	Ok(())
+}
+
+#[test]
+fn terminate_then_kill_terminated_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	let status = child.terminate_then_kill(DIE_TIME * 10)?;
+	assert_eq!(status.signal(), Some(Signal::SIGTERM as i32));
+	Ok(())
+}
+
+#[test]
+fn terminate_then_kill_killed_group() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("trap '' TERM; while true; do sleep 1; done")
+		.group_spawn()?;
+	sleep(DIE_TIME);
+	let status = child.terminate_then_kill(DIE_TIME)?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[test]
+fn wait_or_kill_finished_group() -> Result<()> {
+	let mut child = Command::new("sh").arg("-c").arg("exit 3").group_spawn()?;
+	let status = child.wait_or_kill(DIE_TIME * 10)?;
+	assert_eq!(status.and_then(|s| s.code()), Some(3));
+	Ok(())
+}
+
+#[test]
+fn wait_or_kill_killed_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	assert_eq!(child.wait_or_kill(DIE_TIME)?, None);
+	assert_eq!(child.wait()?.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[test]
+fn kill_handle_from_thread_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	let killer = child.kill_handle()?;
+	std::thread::spawn(move || killer.clone().kill())
+		.join()
+		.unwrap()?;
+	let status = child.wait()?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[test]
+fn wait_timeout_returns_early_group() -> Result<()> {
+	let mut child = Command::new("sleep").arg("0.1").group_spawn()?;
+	let start = std::time::Instant::now();
+	let status = child.wait_timeout(Duration::from_secs(10))?;
+	assert!(status.is_some_and(|s| s.success()), "exited successfully");
+	assert!(
+		start.elapsed() < Duration::from_secs(5),
+		"didn't wait for the timeout"
+	);
+	Ok(())
+}
+
+#[test]
+fn kill_on_drop_group() -> Result<()> {
+	let child = Command::new("yes")
+		.stdout(Stdio::null())
+		.group()
+		.kill_on_drop(true)
+		.spawn()?;
+	let pgid = Pid::from_raw(child.id() as _);
+	drop(child);
+
+	// dropping doesn't wait for the group to exit, so the leader may still need reaping
+	let reaped = waitpid(pgid, None);
+	assert!(
+		matches!(
+			reaped,
+			Ok(WaitStatus::Signaled(_, Signal::SIGKILL, _)) | Err(Errno::ECHILD)
+		),
+		"{reaped:?}"
+	);
+	assert_eq!(killpg(pgid, None), Err(Errno::ESRCH));
+	Ok(())
+}
+
+#[test]
+fn kill_on_drop_signal_group() -> Result<()> {
+	let child = Command::new("yes")
+		.stdout(Stdio::null())
+		.group()
+		.kill_on_drop_signal(Signal::SIGTERM)
+		.spawn()?;
+	let pgid = Pid::from_raw(child.id() as _);
+	drop(child);
+
+	let reaped = waitpid(pgid, None);
+	assert!(
+		matches!(
+			reaped,
+			Ok(WaitStatus::Signaled(_, Signal::SIGTERM, _)) | Err(Errno::ECHILD)
+		),
+		"{reaped:?}"
+	);
+	assert_eq!(killpg(pgid, None), Err(Errno::ESRCH));
+	Ok(())
+}
+
+#[test]
+fn into_inner_disarms_kill_on_drop_group() -> Result<()> {
+	let mut child = Command::new("yes")
+		.stdout(Stdio::null())
+		.group()
+		.kill_on_drop(true)
+		.spawn()?
+		.into_inner();
+	sleep(DIE_TIME);
+	assert!(child.try_wait()?.is_none(), "still running");
+	child.kill()?;
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn detach_group() -> Result<()> {
+	let child = Command::new("yes")
+		.stdout(Stdio::null())
+		.group()
+		.kill_on_drop(true)
+		.spawn()?;
+	let pgid = Pid::from_raw(child.id() as _);
+	child.detach();
+	sleep(DIE_TIME);
+	assert_eq!(killpg(pgid, None), Ok(()), "still running");
+
+	killpg(pgid, Signal::SIGKILL)?;
+	waitpid(pgid, None)?;
+	Ok(())
+}
+
+#[test]
+fn wait_disarms_kill_on_drop_group() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("sleep 10 & exit 0")
+		.stdout(Stdio::null())
+		.group()
+		.kill_on_drop(true)
+		.spawn()?;
+	let pgid = Pid::from_raw(child.id() as _);
+	sleep(DIE_TIME);
+	assert!(child.try_wait()?.is_some(), "leader exited");
+	drop(child);
+
+	// the leader was reaped, so the lingering sleep isn't killed
+	assert_eq!(killpg(pgid, None), Ok(()));
+	killpg(pgid, Signal::SIGKILL)?;
+	Ok(())
+}
+
+#[test]
+fn new_session_group() -> Result<()> {
+	let mut child = Command::new("yes")
+		.stdout(Stdio::null())
+		.group()
+		.new_session(true)
+		.spawn()?;
+	let pid = Pid::from_raw(child.id() as _);
+	assert_eq!(getsid(Some(pid))?, pid, "session leader");
+	assert_eq!(getpgid(Some(pid))?, pid, "group leader");
+	child.kill()?;
+	let status = child.wait()?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[test]
+fn spawn_twice_group() -> Result<()> {
+	let mut command = Command::new("sh");
+	command.arg("-c").arg("echo out; echo err >&2");
+	let mut builder = command.group();
+	builder
+		.new_session(true)
+		.merge_stderr_into_stdout(true)
+		.stdout(Stdio::piped());
+
+	let first = builder.spawn()?;
+	let second = builder.spawn()?;
+	assert_ne!(first.id(), second.id());
+	for child in [first, second] {
+		let pid = Pid::from_raw(child.id() as _);
+		assert_eq!(getsid(Some(pid))?, pid, "session leader");
+		let output = child.wait_with_output()?;
+		assert!(output.status.success());
+		assert_eq!(output.stdout, b"out\nerr\n".to_vec());
+	}
+	Ok(())
+}
+
+#[test]
+fn spawn_again_with_new_session_group() -> Result<()> {
+	let mut command = Command::new("yes");
+	command.stdout(Stdio::null());
+	let mut builder = command.group();
+
+	let mut first = builder.new_session(false).spawn()?;
+	let pid = Pid::from_raw(first.id() as _);
+	assert_ne!(getsid(Some(pid))?, pid, "not a session leader");
+	assert_eq!(getpgid(Some(pid))?, pid, "group leader");
+
+	let mut second = builder.new_session(true).spawn()?;
+	let pid = Pid::from_raw(second.id() as _);
+	assert_eq!(getsid(Some(pid))?, pid, "session leader");
+
+	for child in [&mut first, &mut second] {
+		child.kill()?;
+		child.wait()?;
+	}
+	Ok(())
+}
+
+#[test]
+fn group_spawn_after_new_session_group() -> Result<()> {
+	let mut command = Command::new("yes");
+	command.stdout(Stdio::null());
+
+	let mut first = command.group().new_session(true).spawn()?;
+	let mut second = command.group_spawn()?;
+	let pid = Pid::from_raw(second.id() as _);
+	assert_ne!(getsid(Some(pid))?, pid, "not a session leader");
+	assert_eq!(getpgid(Some(pid))?, pid, "group leader");
+
+	for child in [&mut first, &mut second] {
+		child.kill()?;
+		child.wait()?;
+	}
+	Ok(())
+}
+
+#[test]
+fn group_output_after_nice_group() -> Result<()> {
+	let base = Command::new("nice").output()?.stdout;
+
+	let mut command = Command::new("nice");
+	command.stdout(Stdio::piped());
+	let output = command.group().nice(5).spawn()?.wait_with_output()?;
+	assert_ne!(output.stdout, base, "niceness applied");
+
+	let output = command.group_output()?;
+	assert!(output.status.success());
+	assert_eq!(output.stdout, base, "earlier niceness not applied");
+	Ok(())
+}
+
+#[test]
+fn new_session_overrides_process_group_group() -> Result<()> {
+	let mut other = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	let mut child = Command::new("yes")
+		.stdout(Stdio::null())
+		.group()
+		.process_group(other.id() as _)
+		.new_session(true)
+		.spawn()?;
+	let pid = Pid::from_raw(child.id() as _);
+	assert_eq!(getsid(Some(pid))?, pid, "session leader");
+	assert_eq!(getpgid(Some(pid))?, pid, "group leader");
+	child.kill()?;
+	child.wait()?;
+	assert!(other.try_wait()?.is_none(), "other group untouched");
+	other.kill()?;
+	other.wait()?;
+	Ok(())
+}
+
+#[test]
+fn new_session_already_leader_group() -> Result<()> {
+	use std::os::unix::process::CommandExt;
+
+	let mut child = Command::new("yes")
+		.stdout(Stdio::null())
+		.process_group(0)
+		.group()
+		.new_session(true)
+		.spawn()?;
+	let pid = Pid::from_raw(child.id() as _);
+	assert_ne!(getsid(Some(pid))?, pid, "no new session");
+	assert_eq!(getpgid(Some(pid))?, pid, "group leader");
+	child.kill()?;
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn group_id_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	let pid = Pid::from_raw(child.id() as _);
+	assert_eq!(getpgid(Some(pid))?.as_raw() as u32, child.group_id());
+	assert_eq!(child.group_id(), child.id());
+	assert_eq!(child.leader_id(), child.id());
+	child.kill()?;
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn process_group_group() -> Result<()> {
+	let mut leader = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	let pgid = Pid::from_raw(leader.id() as _);
+	let mut member = Command::new("yes")
+		.stdout(Stdio::null())
+		.group()
+		.process_group(pgid.as_raw())
+		.spawn()?;
+	assert_eq!(getpgid(Some(Pid::from_raw(member.id() as _)))?, pgid);
+	assert_eq!(member.group_id(), leader.group_id());
+	assert_ne!(member.group_id(), member.id());
+
+	// signalling the member hits the whole shared group
+	member.kill()?;
+	let status = member.wait()?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	let status = leader.wait()?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[test]
+fn process_group_missing_group() {
+	let res = Command::new("yes")
+		.stdout(Stdio::null())
+		.group()
+		.process_group(i32::MAX)
+		.spawn();
+	assert!(res.is_err(), "group doesn't exist");
+}
+
+#[test]
+fn wait_with_rusage_group() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("i=0; while [ $i -lt 100000 ]; do i=$((i+1)); done; exit 3")
+		.group_spawn()?;
+	let (status, usage) = child.wait_with_rusage()?;
+	assert_eq!(status.code(), Some(3));
+	assert!(
+		usage.user_time + usage.system_time > Duration::ZERO,
+		"used some CPU"
+	);
+	assert!(usage.max_rss > 0, "used some memory");
+	assert_eq!(child.wait()?, status, "cached status");
+	Ok(())
+}
+
+#[test]
+fn wait_with_output_large_both_group() -> Result<()> {
+	// stderr is written first, so reading stdout to the end first would deadlock
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("head -c 1000000 /dev/zero >&2; head -c 1000000 /dev/zero")
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.group_spawn()?;
+
+	let output = child.wait_with_output()?;
+	assert!(output.status.success());
+	assert_eq!(output.stdout.len(), 1_000_000);
+	assert_eq!(output.stderr.len(), 1_000_000);
+	Ok(())
+}
+
+#[test]
+fn process_count_group() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("sleep 10 & sleep 10 & wait")
+		.group_spawn()?;
+	sleep(DIE_TIME);
+	if cfg!(target_os = "linux") {
+		assert_eq!(child.process_count()?, 3);
+	}
+
+	child.kill()?;
+	child.wait()?;
+	sleep(DIE_TIME);
+	if cfg!(target_os = "linux") {
+		assert_eq!(child.process_count()?, 0);
+	}
+	Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn pidfd_group() -> Result<()> {
+	use nix::poll::{poll, PollFd, PollFlags};
+
+	let mut child = Command::new("sh").arg("-c").arg("exit 3").group_spawn()?;
+	let readable = {
+		let Some(fd) = child.pidfd() else {
+			// pidfds aren't supported by this kernel
+			return Ok(());
+		};
+		let mut fds = [PollFd::new(&fd, PollFlags::POLLIN)];
+		poll(&mut fds, 1000)?
+	};
+	assert_eq!(readable, 1, "leader exited");
+
+	let status = child.try_wait_group()?.expect("group exited");
+	assert_eq!(status.code(), Some(3));
+	Ok(())
+}
+
+#[test]
+fn try_wait_group_group() -> Result<()> {
+	let mut child = Command::new("sh").arg("-c").arg("exit 3").group_spawn()?;
+	let status = loop {
+		if let Some(status) = child.try_wait_group()? {
+			break status;
+		}
+		sleep(Duration::from_millis(1));
+	};
+	assert_eq!(status.code(), Some(3));
+	assert_eq!(child.wait()?.code(), Some(3));
+	Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn pids_group() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("sleep 10 & wait")
+		.group_spawn()?;
+	sleep(DIE_TIME);
+	let pids = child.pids()?;
+	assert_eq!(pids.len(), 2);
+	assert!(pids.contains(&child.id()), "includes the leader");
+	assert!(pids[0] < pids[1], "sorted");
+	for pid in pids {
+		assert_eq!(
+			getpgid(Some(Pid::from_raw(pid as _)))?.as_raw() as u32,
+			child.id()
+		);
+	}
+
+	child.kill()?;
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn foreground_without_terminal_group() -> Result<()> {
+	// a new session has no controlling terminal to be in the foreground of
+	let spawned = Command::new("true")
+		.group()
+		.new_session(true)
+		.foreground(true)
+		.spawn();
+	assert!(spawned.is_err());
+	Ok(())
+}
+
+#[test]
+fn wait_status_group() -> Result<()> {
+	let mut child = Command::new("sleep").arg("10").group_spawn()?;
+
+	child.signal(Signal::SIGSTOP)?;
+	assert_eq!(child.wait_status()?, GroupStatus::Stopped(Signal::SIGSTOP));
+	child.signal(Signal::SIGCONT)?;
+	assert_eq!(child.wait_status()?, GroupStatus::Continued);
+	child.kill()?;
+	assert_eq!(child.wait_status()?, GroupStatus::Signaled(Signal::SIGKILL));
+	assert_eq!(child.wait()?.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[test]
+fn wait_status_exited_group() -> Result<()> {
+	let mut child = Command::new("sh").arg("-c").arg("exit 3").group_spawn()?;
+	assert_eq!(child.wait_status()?, GroupStatus::Exited(3));
+	assert_eq!(child.wait_status()?, GroupStatus::Exited(3));
+	Ok(())
+}
+
+#[test]
+fn merge_stderr_into_stdout_group() -> Result<()> {
+	let output = Command::new("sh")
+		.arg("-c")
+		.arg("echo out1; echo err1 >&2; echo out2")
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.group()
+		.merge_stderr_into_stdout(true)
+		.spawn()?
+		.wait_with_output()?;
+	assert!(output.status.success());
+	assert_eq!(output.stdout, b"out1\nerr1\nout2\n");
+	assert!(output.stderr.is_empty());
+	Ok(())
+}
+
+#[test]
+fn id_checked_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	assert_eq!(child.id_checked(), Some(child.id()));
+	assert!(child.try_wait()?.is_none());
+	assert_eq!(child.id_checked(), Some(child.id()), "still running");
+
+	child.kill()?;
+	child.wait()?;
+	assert_eq!(child.id_checked(), None, "reaped");
+	Ok(())
+}
+
+#[test]
+fn adopt_group() -> Result<()> {
+	use std::os::unix::process::CommandExt;
+
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("sleep 10 & wait")
+		.process_group(0)
+		.spawn()?;
+	let mut child = GroupChild::adopt(child).map_err(|(err, _)| err)?;
+	let pid = Pid::from_raw(child.id() as _);
+	assert_eq!(getpgid(Some(pid))?, pid, "leads its group");
+	sleep(DIE_TIME);
+
+	child.kill()?;
+	let status = child.wait()?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[test]
+fn adopt_in_our_group_group() -> Result<()> {
+	let child = Command::new("yes").stdout(Stdio::null()).spawn()?;
+	let id = child.id();
+	let (err, mut child) = GroupChild::adopt(child).expect_err("already running its program");
+	assert_eq!(err.raw_os_error(), Some(Errno::EACCES as i32));
+	assert_eq!(child.id(), id, "given back");
+	assert!(child.try_wait()?.is_none(), "still running");
+
+	child.kill()?;
+	let status = child.wait()?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[test]
+fn debug_group() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("exit 3")
+		.stdout(Stdio::piped())
+		.group_spawn()?;
+	let id = child.id();
+	let debug = format!("{child:?}");
+	assert!(debug.contains(&format!("id: {id}")), "{debug}");
+	assert!(debug.contains("exit_status: None"), "{debug}");
+	assert!(debug.contains("stdin: false"), "{debug}");
+	assert!(debug.contains("stdout: true"), "{debug}");
+
+	child.wait()?;
+	let debug = format!("{child:?}");
+	assert!(debug.contains("exit_status: Some"), "{debug}");
+	Ok(())
+}
+
+#[test]
+fn stream_output_group() -> Result<()> {
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("printf 'one\\ntw'; sleep 0.1; printf 'o\\r\\n'; echo err >&2; printf end; exit 3")
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.group_spawn()?;
+
+	let mut lines = Vec::new();
+	let status = child.stream_output(|line| lines.push(line))?;
+	assert_eq!(status.code(), Some(3));
+
+	let from = |source| {
+		lines
+			.iter()
+			.filter(|line| line.source == source)
+			.map(|line| line.line.as_slice())
+			.collect::<Vec<_>>()
+	};
+	assert_eq!(from(OutputSource::Stdout), [&b"one"[..], b"two", b"end"]);
+	assert_eq!(from(OutputSource::Stderr), [b"err"]);
+	Ok(())
+}
+
+#[test]
+fn stream_output_stdout_only_group() -> Result<()> {
+	let child = Command::new("echo")
+		.arg("hello")
+		.stdout(Stdio::piped())
+		.group_spawn()?;
+
+	let mut lines = Vec::new();
+	assert!(child.stream_output(|line| lines.push(line))?.success());
+	assert_eq!(
+		lines,
+		[OutputLine {
+			source: OutputSource::Stdout,
+			line: b"hello".to_vec(),
+		}]
+	);
+	Ok(())
+}
+
+#[test]
+fn wait_with_output_interrupted_group() -> Result<()> {
+	use nix::{
+		libc,
+		sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet},
+	};
+
+	extern "C" fn ignore(_: libc::c_int) {}
+	// no SA_RESTART, so that blocking calls fail with EINTR
+	let action = SigAction::new(
+		SigHandler::Handler(ignore),
+		SaFlags::empty(),
+		SigSet::empty(),
+	);
+	unsafe { sigaction(Signal::SIGUSR2, &action) }?;
+
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("sleep 0.2; echo out; echo err >&2")
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.group_spawn()?;
+
+	let this = unsafe { libc::pthread_self() };
+	let signaller = std::thread::spawn(move || {
+		sleep(Duration::from_millis(50));
+		unsafe { libc::pthread_kill(this, libc::SIGUSR2) };
+	});
+
+	let output = child.wait_with_output()?;
+	signaller.join().expect("signaller panicked");
+	assert!(output.status.success());
+	assert_eq!(output.stdout, b"out\n");
+	assert_eq!(output.stderr, b"err\n");
+	Ok(())
+}
+
+#[test]
+fn communicate_group() -> Result<()> {
+	let input = vec![b'x'; 1_000_000];
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("cat; echo done >&2")
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.group_spawn()?;
+	let output = child.communicate(&input)?;
+	assert!(output.status.success());
+	assert_eq!(output.stdout, input);
+	assert_eq!(output.stderr, b"done\n");
+	Ok(())
+}
+
+#[test]
+fn communicate_unread_input_group() -> Result<()> {
+	let child = Command::new("true").stdin(Stdio::piped()).group_spawn()?;
+	let output = child.communicate(&vec![b'x'; 1_000_000])?;
+	assert!(output.status.success());
+	Ok(())
+}
+
+#[test]
+fn wait_with_output_limited_group() -> Result<()> {
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("head -c 200000 /dev/zero >&2; head -c 300000 /dev/zero; exit 3")
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.group_spawn()?;
+	let limited = child.wait_with_output_limited(100_000, 1_000)?;
+	assert!(limited.stdout_truncated, "stdout truncated");
+	assert!(limited.stderr_truncated, "stderr truncated");
+	assert_eq!(limited.output.stdout.len(), 100_000);
+	assert_eq!(limited.output.stderr.len(), 1_000);
+	assert_eq!(limited.output.status.code(), Some(3), "drained, not killed");
+	Ok(())
+}
+
+#[test]
+fn wait_with_output_limited_not_reached_group() -> Result<()> {
+	let child = Command::new("echo")
+		.arg("hello")
+		.stdout(Stdio::piped())
+		.group_spawn()?;
+	let limited = child.wait_with_output_limited(6, 0)?;
+	assert!(!limited.stdout_truncated, "not truncated");
+	assert!(!limited.stderr_truncated, "not piped");
+	assert!(limited.output.status.success());
+	assert_eq!(limited.output.stdout, b"hello\n");
+	Ok(())
+}
+
 #[test]
 fn wait_after_die_normal() -> Result<()> {
 	let mut child = Command::new("echo").stdout(Stdio::null()).spawn()?;
@@ -242,6 +1299,36 @@ fn wait_group() -> Result<()> {
 	Ok(())
 }
 
+#[test]
+fn wait_all_group() -> Result<()> {
+	let mut child = Command::new("sh").arg("-c").arg("exit 3").group_spawn()?;
+	let leader = child.id();
+	let all = child.wait_all()?;
+	assert_eq!(all.len(), 1, "only the leader is our child");
+	assert_eq!(all[0].0, leader);
+	assert_eq!(all[0].1.code(), Some(3));
+	assert_eq!(child.wait()?.code(), Some(3), "wait after wait_all");
+	Ok(())
+}
+
+#[test]
+fn wait_worst_group() -> Result<()> {
+	let mut child = Command::new("sleep").arg("0.5").group_spawn()?;
+	let _member = Command::new("sh")
+		.arg("-c")
+		.arg("exit 5")
+		.group()
+		.process_group(child.group_id() as _)
+		.spawn()?;
+
+	assert_eq!(child.wait_worst()?.code(), Some(5), "member failed");
+	assert!(child.wait()?.success(), "wait is still the leader's");
+
+	let mut child = Command::new("sh").arg("-c").arg("exit 3").group_spawn()?;
+	assert_eq!(child.wait_worst()?.code(), Some(3), "leader failed");
+	Ok(())
+}
+
 #[test]
 fn wait_with_output_normal() -> Result<()> {
 	let child = Command::new("echo")
@@ -307,3 +1394,100 @@ fn signal_group() -> Result<()> {
 
 	Ok(())
 }
+
+#[test]
+fn erased_start_kill_normal() -> Result<()> {
+	let mut child: ErasedChild = Command::new("yes").stdout(Stdio::null()).spawn()?.into();
+	child.start_kill()?;
+	let status = child.wait()?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[test]
+fn erased_start_kill_group() -> Result<()> {
+	let mut child: ErasedChild = Command::new("yes")
+		.stdout(Stdio::null())
+		.group_spawn()?
+		.into();
+	child.start_kill()?;
+	let status = child.wait()?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[test]
+fn erased_id_group() -> Result<()> {
+	let mut child: ErasedChild = Command::new("yes")
+		.stdout(Stdio::null())
+		.group_spawn()?
+		.into();
+	let shared: &ErasedChild = &child;
+	let id = shared.id();
+	assert_eq!(child.inner().id(), id);
+	child.kill()?;
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn erased_inner_read_stdout_normal() -> Result<()> {
+	let mut child = ErasedChild::Ungrouped(
+		Command::new("echo")
+			.arg("hello")
+			.stdout(Stdio::piped())
+			.spawn()?,
+	);
+	assert_eq!(child.inner().id(), child.id());
+
+	let mut output = String::new();
+	if let Some(mut out) = child.inner().stdout.take() {
+		out.read_to_string(&mut output)?;
+	}
+
+	assert_eq!(output.as_str(), "hello\n");
+	child.into_inner().wait()?;
+	Ok(())
+}
+
+#[test]
+fn erased_inner_read_stdout_group() -> Result<()> {
+	let mut child = ErasedChild::Grouped(
+		Command::new("echo")
+			.arg("hello")
+			.stdout(Stdio::piped())
+			.group_spawn()?,
+	);
+	assert_eq!(child.inner().id(), child.id());
+
+	let mut output = String::new();
+	if let Some(mut out) = child.inner().stdout.take() {
+		out.read_to_string(&mut output)?;
+	}
+
+	assert_eq!(output.as_str(), "hello\n");
+	child.into_inner().wait()?;
+	Ok(())
+}
+
+#[test]
+fn spawn_erased_normal() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("exit 3")
+		.spawn_erased(false)?;
+	assert!(matches!(child, ErasedChild::Ungrouped(_)));
+	assert_eq!(child.wait()?.code(), Some(3));
+	Ok(())
+}
+
+#[test]
+fn spawn_erased_group() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("exit 3")
+		.spawn_erased(true)?;
+	assert!(matches!(child, ErasedChild::Grouped(_)));
+	assert_eq!(child.wait()?.code(), Some(3));
+	Ok(())
+}
@@ -1,8 +1,11 @@
 #![cfg(windows)]
 
-use command_group::CommandGroup;
+use command_group::{
+	builder::PriorityClass, stdlib::ErasedChild, CommandGroup, ControlEvent, GroupChild, Signal,
+};
 use std::{
-	io::{Read, Result, Write},
+	io::{ErrorKind, Read, Result, Write},
+	os::windows::io::AsRawHandle,
 	process::{Command, Stdio},
 	thread::sleep,
 	time::Duration,
@@ -46,6 +49,36 @@ fn inner_read_stdout_group() -> Result<()> {
 	Ok(())
 }
 
+#[test]
+fn read_stdout_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("echo hello")
+		.stdout(Stdio::piped())
+		.group_spawn()?;
+
+	let mut output = String::new();
+	child.read_to_string(&mut output)?;
+	assert_eq!(output.as_str(), "hello\r\n");
+	assert!(child.wait()?.success());
+	Ok(())
+}
+
+#[test]
+fn spawn_twice_group() -> Result<()> {
+	let mut command = Command::new("powershell.exe");
+	command.arg("/C").arg("exit 3");
+	let mut builder = command.group();
+	builder.kill_on_drop(true);
+
+	let mut first = builder.spawn()?;
+	let mut second = builder.spawn()?;
+	assert_ne!(first.id(), second.id());
+	assert_eq!(first.wait()?.code(), Some(3));
+	assert_eq!(second.wait()?.code(), Some(3));
+	Ok(())
+}
+
 #[test]
 fn into_inner_write_stdin_normal() -> Result<()> {
 	let mut child = Command::new("findstr")
@@ -68,6 +101,7 @@ fn into_inner_write_stdin_normal() -> Result<()> {
 }
 
 #[test]
+#[allow(clippy::zombie_processes)]
 fn into_inner_write_stdin_group() -> Result<()> {
 	let mut child = Command::new("findstr")
 		.arg("^")
@@ -119,6 +153,143 @@ fn kill_and_try_wait_group() -> Result<()> {
 	Ok(())
 }
 
+#[test]
+fn start_kill_and_wait_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	child.start_kill()?;
+	let status = child.wait()?;
+	assert_eq!(status.code(), Some(1));
+	Ok(())
+}
+
+#[test]
+fn terminate_and_try_wait_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	assert!(child.try_wait()?.is_none());
+	child.terminate()?;
+	sleep(DIE_TIME);
+	assert!(child.try_wait()?.is_some());
+	Ok(())
+}
+
+#[test]
+fn kill_with_and_wait_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	assert!(
+		child.kill_with(Signal::Interrupt).is_err(),
+		"not a new process group"
+	);
+	child.kill_with(Signal::Kill)?;
+	let status = child.wait()?;
+	assert_eq!(status.code(), Some(1));
+	Ok(())
+}
+
+#[test]
+fn send_control_group() -> Result<()> {
+	let mut child = Command::new("ping")
+		.args(["-t", "localhost"])
+		.stdout(Stdio::null())
+		.group()
+		.new_process_group(true)
+		.spawn()?;
+	child.send_ctrl_break()?;
+	let status = child.wait_timeout(DIE_TIME * 10)?;
+	assert!(status.is_some(), "ping exited on ctrl-break");
+
+	let err = child.send_control(ControlEvent::CtrlBreak).unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	Ok(())
+}
+
+#[test]
+fn send_control_without_new_process_group_group() -> Result<()> {
+	let mut child = Command::new("ping")
+		.args(["-t", "localhost"])
+		.stdout(Stdio::null())
+		.group_spawn()?;
+	let err = child.send_control(ControlEvent::CtrlBreak).unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::Unsupported);
+	child.kill()?;
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn detach_group() -> Result<()> {
+	let child = Command::new("ping")
+		.args(["-t", "localhost"])
+		.stdout(Stdio::null())
+		.group()
+		.kill_on_drop(true)
+		.spawn()?;
+	let pid = child.id();
+	child.detach();
+	sleep(DIE_TIME);
+
+	// only succeeds if it's still running
+	let status = Command::new("taskkill")
+		.args(["/F", "/PID", &pid.to_string()])
+		.stdout(Stdio::null())
+		.status()?;
+	assert!(status.success(), "still running after detach");
+	Ok(())
+}
+
+#[test]
+fn adopt_group() -> Result<()> {
+	let child = Command::new("ping")
+		.args(["-t", "localhost"])
+		.stdout(Stdio::null())
+		.spawn()?;
+	let mut child = GroupChild::adopt(child).map_err(|(err, _)| err)?;
+	assert_eq!(child.process_count()?, 1);
+
+	child.kill()?;
+	let status = child.wait_timeout(DIE_TIME * 10)?;
+	assert!(status.is_some(), "killed through the job");
+	Ok(())
+}
+
+#[test]
+fn wait_timeout_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	assert!(child.wait_timeout(DIE_TIME)?.is_none(), "timed out");
+	child.kill()?;
+	let status = child.wait_timeout(DIE_TIME * 10)?;
+	assert!(status.is_some(), "exited after kill");
+	assert_eq!(child.wait_timeout(DIE_TIME)?, status, "second wait_timeout");
+	assert_eq!(Some(child.wait()?), status, "wait after wait_timeout");
+	Ok(())
+}
+
+#[test]
+fn kill_handle_from_thread_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	let killer = child.kill_handle()?;
+	std::thread::spawn(move || killer.clone().kill())
+		.join()
+		.unwrap()?;
+	sleep(DIE_TIME);
+	assert!(child.try_wait()?.is_some());
+	Ok(())
+}
+
 #[test]
 fn wait_after_die_normal() -> Result<()> {
 	let mut child = Command::new("powershell.exe")
@@ -233,6 +404,23 @@ fn wait_with_output_group() -> Result<()> {
 	Ok(())
 }
 
+#[test]
+fn wait_with_output_large_both_group() -> Result<()> {
+	// stderr is written first, so reading stdout to the end first would deadlock
+	let child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("[Console]::Error.Write('e' * 1000000); [Console]::Out.Write('o' * 1000000)")
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.group_spawn()?;
+
+	let output = child.wait_with_output()?;
+	assert!(output.status.success());
+	assert_eq!(output.stdout, vec![b'o'; 1_000_000]);
+	assert_eq!(output.stderr, vec![b'e'; 1_000_000]);
+	Ok(())
+}
+
 #[test]
 fn id_same_as_inner_group() -> Result<()> {
 	let mut child = Command::new("powershell.exe")
@@ -240,5 +428,426 @@ fn id_same_as_inner_group() -> Result<()> {
 		.arg("echo hello")
 		.group_spawn()?;
 	assert_eq!(child.id(), child.inner().id());
+	assert_eq!(child.group_id(), child.id());
+	assert_eq!(child.leader_id(), child.id());
+	Ok(())
+}
+
+#[test]
+fn erased_start_kill_normal() -> Result<()> {
+	let mut child = ErasedChild::Ungrouped(
+		Command::new("powershell.exe")
+			.arg("/C")
+			.arg("pause")
+			.spawn()?,
+	);
+	child.start_kill()?;
+	let status = child.wait()?;
+	assert_eq!(status.code(), Some(1));
+	Ok(())
+}
+
+#[test]
+fn erased_start_kill_group() -> Result<()> {
+	let mut child = ErasedChild::Grouped(
+		Command::new("powershell.exe")
+			.arg("/C")
+			.arg("pause")
+			.group_spawn()?,
+	);
+	child.start_kill()?;
+	let status = child.wait()?;
+	assert_eq!(status.code(), Some(1));
+	Ok(())
+}
+
+#[test]
+fn erased_inner_read_stdout_normal() -> Result<()> {
+	let mut child = ErasedChild::Ungrouped(
+		Command::new("powershell.exe")
+			.arg("/C")
+			.arg("echo hello")
+			.stdout(Stdio::piped())
+			.spawn()?,
+	);
+	assert_eq!(child.inner().id(), child.id());
+
+	let mut output = String::new();
+	if let Some(mut out) = child.inner().stdout.take() {
+		out.read_to_string(&mut output)?;
+	}
+
+	assert_eq!(output.as_str(), "hello\r\n");
+	Ok(())
+}
+
+#[test]
+fn erased_inner_read_stdout_group() -> Result<()> {
+	let mut child = ErasedChild::Grouped(
+		Command::new("powershell.exe")
+			.arg("/C")
+			.arg("echo hello")
+			.stdout(Stdio::piped())
+			.group_spawn()?,
+	);
+	assert_eq!(child.inner().id(), child.id());
+
+	let mut output = String::new();
+	if let Some(mut out) = child.inner().stdout.take() {
+		out.read_to_string(&mut output)?;
+	}
+
+	assert_eq!(output.as_str(), "hello\r\n");
+	Ok(())
+}
+
+#[test]
+fn erased_signal_normal() -> Result<()> {
+	let mut child = ErasedChild::Ungrouped(
+		Command::new("powershell.exe")
+			.arg("/C")
+			.arg("pause")
+			.spawn()?,
+	);
+	assert!(child.signal(Signal::Interrupt).is_err(), "unsupported");
+	child.signal(Signal::Terminate)?;
+	let status = child.wait()?;
+	assert_eq!(status.code(), Some(1));
+	Ok(())
+}
+
+#[test]
+fn erased_signal_group() -> Result<()> {
+	let mut child = ErasedChild::Grouped(
+		Command::new("powershell.exe")
+			.arg("/C")
+			.arg("pause")
+			.group_spawn()?,
+	);
+	assert!(child.signal(Signal::Interrupt).is_err(), "unsupported");
+	child.signal(Signal::Terminate)?;
+	let status = child.wait()?;
+	assert_eq!(status.code(), Some(1));
+	Ok(())
+}
+
+#[test]
+fn job_accounting_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("echo hello")
+		.stdout(Stdio::null())
+		.group_spawn()?;
+	child.wait()?;
+	let accounting = child.job_accounting()?;
+	assert!(accounting.total_processes >= 1, "counted the leader");
+	assert!(accounting.peak_memory > 0, "used some memory");
+	Ok(())
+}
+
+#[test]
+fn job_handle_group() -> Result<()> {
+	use std::{mem, ptr};
+	use winapi::um::{
+		jobapi2::QueryInformationJobObject,
+		winnt::{JobObjectBasicAccountingInformation, JOBOBJECT_BASIC_ACCOUNTING_INFORMATION},
+	};
+
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+
+	let mut info = JOBOBJECT_BASIC_ACCOUNTING_INFORMATION::default();
+	let ret = unsafe {
+		QueryInformationJobObject(
+			child.job_handle().as_raw_handle() as _,
+			JobObjectBasicAccountingInformation,
+			&mut info as *mut _ as _,
+			mem::size_of_val(&info) as _,
+			ptr::null_mut(),
+		)
+	};
+	assert_ne!(ret, 0, "queried the job");
+	assert!(info.ActiveProcesses >= 1, "leader is in the job");
+	assert!(!child.completion_port_handle().as_raw_handle().is_null());
+
+	child.kill()?;
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn job_limits_group() -> Result<()> {
+	use std::{mem, ptr};
+	use winapi::um::{
+		jobapi2::QueryInformationJobObject,
+		winnt::{
+			JobObjectExtendedLimitInformation, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+			JOB_OBJECT_LIMIT_ACTIVE_PROCESS, JOB_OBJECT_LIMIT_JOB_MEMORY,
+			JOB_OBJECT_LIMIT_JOB_TIME,
+		},
+	};
+
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group()
+		.max_processes(4)
+		.memory_limit(1 << 30)
+		.cpu_time_limit(Duration::from_secs(60))
+		.spawn()?;
+
+	let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+	let ret = unsafe {
+		QueryInformationJobObject(
+			child.job_handle().as_raw_handle() as _,
+			JobObjectExtendedLimitInformation,
+			&mut info as *mut _ as _,
+			mem::size_of_val(&info) as _,
+			ptr::null_mut(),
+		)
+	};
+	assert_ne!(ret, 0, "queried the job");
+	let flags = info.BasicLimitInformation.LimitFlags;
+	assert_ne!(flags & JOB_OBJECT_LIMIT_ACTIVE_PROCESS, 0);
+	assert_ne!(flags & JOB_OBJECT_LIMIT_JOB_MEMORY, 0);
+	assert_ne!(flags & JOB_OBJECT_LIMIT_JOB_TIME, 0);
+	assert_eq!(info.BasicLimitInformation.ActiveProcessLimit, 4);
+	assert_eq!(info.JobMemoryLimit, 1 << 30);
+
+	child.kill()?;
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn max_processes_group() -> Result<()> {
+	// the leader can't start any more processes
+	let status = Command::new("cmd")
+		.arg("/C")
+		.arg("cmd /C exit 0")
+		.group()
+		.max_processes(1)
+		.spawn()?
+		.wait()?;
+	assert!(!status.success());
+	Ok(())
+}
+
+#[test]
+fn merge_stderr_into_stdout_group() -> Result<()> {
+	let output = Command::new("cmd")
+		.arg("/C")
+		.arg("echo out&& (echo err)1>&2")
+		.group()
+		.merge_stderr_into_stdout(true)
+		.spawn()?
+		.wait_with_output()?;
+	assert!(output.status.success());
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	assert_eq!(
+		stdout.lines().map(str::trim).collect::<Vec<_>>(),
+		["out", "err"]
+	);
+	assert!(output.stderr.is_empty());
+	Ok(())
+}
+
+#[test]
+fn drop_with_kill_handle_group() -> Result<()> {
+	let first = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	let killer = first.kill_handle()?;
+	let mut second = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+
+	// the kill handle owns a duplicate of the job handle, so it outlives the child
+	drop(first);
+	killer.kill()?;
+	drop(killer);
+
+	// closing the first group's handles didn't touch the second's
+	assert!(second.try_wait()?.is_none());
+	second.kill()?;
+	second.wait()?;
+	Ok(())
+}
+
+#[test]
+fn spawn_normal_after_group() -> Result<()> {
+	let mut command = Command::new("cmd");
+	command.arg("/C").arg("exit 0");
+	assert!(command.group_spawn()?.wait()?.success());
+
+	// the command isn't left with CREATE_SUSPENDED, which would hang it
+	let mut child = command.spawn()?;
+	for _ in 0..50 {
+		if child.try_wait()?.is_some() {
+			return Ok(());
+		}
+		sleep(DIE_TIME);
+	}
+
+	child.kill()?;
+	panic!("child never ran");
+}
+
+#[test]
+fn kill_with_code_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	child.kill_with_code(42)?;
+	let status = child.wait()?;
+	assert_eq!(status.code(), Some(42));
+	Ok(())
+}
+
+#[test]
+fn kill_with_code_then_try_wait_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	child.kill_with_code(137)?;
+	sleep(DIE_TIME);
+	let status = child.try_wait()?.expect("killed");
+	assert_eq!(status.code(), Some(137));
+	assert_eq!(child.wait()?.code(), Some(137), "cached status");
+	Ok(())
+}
+
+#[test]
+fn process_count_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	assert!(child.process_count()? >= 1, "leader is running");
+	child.kill()?;
+	child.wait()?;
+	assert_eq!(child.process_count()?, 0);
+	Ok(())
+}
+
+#[test]
+fn wait_for_members_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("Start-Process -NoNewWindow powershell.exe -ArgumentList '/C','Start-Sleep 1'")
+		.group_spawn()?;
+	assert!(
+		child.wait_timeout(Duration::from_millis(100))?.is_none(),
+		"member running"
+	);
+	assert!(child.wait()?.success());
+	assert_eq!(child.process_count()?, 0);
+	Ok(())
+}
+
+#[test]
+fn try_wait_after_grandchild_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("Start-Process -NoNewWindow -Wait cmd.exe -ArgumentList '/C','exit'; exit 3")
+		.group_spawn()?;
+	sleep(DIE_TIME);
+
+	let status = child.try_wait()?.expect("exited");
+	assert_eq!(status.code(), Some(3));
+	assert_eq!(child.process_count()?, 0);
+	Ok(())
+}
+
+#[test]
+fn pids_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	assert!(child.pids()?.contains(&child.id()), "includes the leader");
+	child.kill()?;
+	child.wait()?;
+	assert!(child.pids()?.is_empty());
+	Ok(())
+}
+
+#[test]
+fn priority_class_group() -> Result<()> {
+	let output = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("(Get-Process -Id $PID).PriorityClass")
+		.group()
+		.priority_class(PriorityClass::BelowNormal)
+		.stdout(Stdio::piped())
+		.spawn()?
+		.wait_with_output()?;
+	assert!(output.status.success());
+	assert_eq!(
+		String::from_utf8_lossy(&output.stdout).trim(),
+		"BelowNormal"
+	);
+	Ok(())
+}
+
+#[test]
+fn no_window_group() -> Result<()> {
+	let output = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("(Get-Process -Id $PID).PriorityClass")
+		.group()
+		.no_window(true)
+		.creation_flags(0x0000_8000) // ABOVE_NORMAL_PRIORITY_CLASS
+		.stdout(Stdio::piped())
+		.spawn()?
+		.wait_with_output()?;
+	assert!(output.status.success());
+	assert_eq!(
+		String::from_utf8_lossy(&output.stdout).trim(),
+		"AboveNormal",
+		"creation flags are kept"
+	);
+	Ok(())
+}
+
+#[test]
+fn allow_breakaway_group() -> Result<()> {
+	let spawned = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group()
+		.allow_breakaway(true)
+		.spawn();
+
+	match spawned {
+		Ok(mut child) => {
+			assert!(child.pids()?.contains(&child.id()), "leader is in our job");
+			child.kill()?;
+			child.wait()?;
+		}
+		// the test runner's own job, if any, may not allow breakaway
+		Err(err) => assert_eq!(err.raw_os_error(), Some(5), "access denied"),
+	}
+	Ok(())
+}
+
+#[test]
+fn kill_after_wait_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	child.kill()?;
+	child.wait()?;
+
+	let err = child.kill().expect_err("group already exited");
+	assert!(matches!(err, command_group::Error::AlreadyExited));
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
 	Ok(())
 }
@@ -1,11 +1,22 @@
 #![cfg(all(unix, feature = "with-tokio"))]
 
-use command_group::{AsyncCommandGroup, Signal, UnixChildExt};
-use std::{io::Result, os::unix::process::ExitStatusExt, process::Stdio, time::Duration};
+use command_group::{
+	tokio::{ErasedChild, GroupSet},
+	AsyncCommandGroup, OutputSource, Resource, Signal, UnixChildExt,
+};
+use nix::{
+	errno::Errno,
+	sys::signal::killpg,
+	unistd::{getpgid, getsid, Pid},
+};
+use std::{
+	future::IntoFuture, io::Result, os::unix::process::ExitStatusExt, process::Stdio,
+	time::Duration,
+};
 use tokio::{
 	io::{AsyncReadExt, AsyncWriteExt},
 	process::Command,
-	time::sleep,
+	time::{sleep, timeout},
 };
 
 const DIE_TIME: Duration = Duration::from_millis(100);
@@ -44,6 +55,53 @@ async fn inner_read_stdout_group() -> Result<()> {
 	Ok(())
 }
 
+#[tokio::test]
+async fn builder_stdio_group() -> Result<()> {
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("cat; echo oops >&2")
+		.group()
+		.kill_on_drop(true)
+		.stdin(Stdio::null())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::null())
+		.spawn()?;
+
+	let output = child.wait_with_output().await?;
+	assert!(output.status.success());
+	assert!(output.stdout.is_empty(), "stdin was null");
+	assert!(output.stderr.is_empty(), "stderr wasn't piped");
+	Ok(())
+}
+
+#[tokio::test]
+async fn builder_rlimit_group() -> Result<()> {
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("ulimit -Sn; ulimit -Hn")
+		.group()
+		.rlimit(Resource::RLIMIT_NOFILE, 64, 128)
+		.stdout(Stdio::piped())
+		.spawn()?;
+
+	let output = child.wait_with_output().await?;
+	assert!(output.status.success());
+	assert_eq!(output.stdout, b"64\n128\n");
+	Ok(())
+}
+
+#[tokio::test]
+async fn into_group_group() -> Result<()> {
+	let mut command = Command::new("sh");
+	command.arg("-c").arg("exit 3");
+	let mut builder = command.into_group();
+	builder.kill_on_drop(true);
+
+	let mut child = builder.spawn()?;
+	assert_eq!(child.wait().await?.code(), Some(3));
+	Ok(())
+}
+
 #[tokio::test]
 async fn into_inner_write_stdin_normal() -> Result<()> {
 	let mut child = Command::new("cat")
@@ -173,6 +231,411 @@ async fn wait_twice_after_sigterm_group() -> Result<()> {
 	Ok(())
 }
 
+#[tokio::test]
+async fn start_kill_and_wait_normal() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).spawn()?;
+	child.start_kill()?;
+	let status = child.wait().await?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[tokio::test]
+async fn start_kill_and_wait_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	child.start_kill()?;
+	let status = child.wait().await?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[tokio::test]
+async fn terminate_and_wait_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	assert!(child.try_wait()?.is_none(), "pre try_wait");
+	child.terminate()?;
+	let status = child.wait().await?;
+	assert_eq!(status.signal(), Some(Signal::SIGTERM as i32));
+	Ok(())
+}
+
+#[tokio::test]
+async fn terminate_then_kill_terminated_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	let status = child.terminate_then_kill(DIE_TIME * 10).await?;
+	assert_eq!(status.signal(), Some(Signal::SIGTERM as i32));
+	Ok(())
+}
+
+#[tokio::test]
+async fn terminate_then_kill_killed_group() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("trap '' TERM; while true; do sleep 1; done")
+		.group_spawn()?;
+	sleep(DIE_TIME).await;
+	let status = child.terminate_then_kill(DIE_TIME).await?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[tokio::test]
+async fn wait_or_kill_finished_group() -> Result<()> {
+	let mut child = Command::new("sh").arg("-c").arg("exit 3").group_spawn()?;
+	let status = child.wait_or_kill(DIE_TIME * 10).await?;
+	assert_eq!(status.and_then(|s| s.code()), Some(3));
+	Ok(())
+}
+
+#[tokio::test]
+async fn wait_or_kill_killed_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	assert_eq!(child.wait_or_kill(DIE_TIME).await?, None);
+	assert_eq!(child.wait().await?.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[tokio::test]
+async fn kill_handle_from_task_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	let killer = child.kill_handle()?;
+	tokio::spawn(async move { killer.kill() }).await??;
+	let status = child.wait().await?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[test]
+fn wait_blocking_group() -> Result<()> {
+	let rt = tokio::runtime::Runtime::new()?;
+	let mut child = rt.block_on(async {
+		Command::new("sh")
+			.arg("-c")
+			.arg("sleep 0.1; exit 3")
+			.group_spawn()
+	})?;
+
+	// from outside of any task
+	let status = child.wait_blocking()?;
+	assert_eq!(status.code(), Some(3));
+	assert_eq!(child.wait_blocking()?.code(), Some(3), "cached");
+	assert_eq!(
+		rt.block_on(child.wait())?.code(),
+		Some(3),
+		"cached for wait()"
+	);
+	Ok(())
+}
+
+#[tokio::test]
+async fn group_set_group() -> Result<()> {
+	let mut set = GroupSet::new();
+	set.insert(
+		"slow",
+		Command::new("sh")
+			.arg("-c")
+			.arg("sleep 0.3; exit 2")
+			.group_spawn()?,
+	);
+	set.insert(
+		"fast",
+		Command::new("sh").arg("-c").arg("exit 1").group_spawn()?,
+	);
+	assert_eq!(set.len(), 2);
+
+	let (key, status) = set.next_exited().await.expect("two in the set");
+	assert_eq!(key, "fast");
+	assert_eq!(status?.code(), Some(1));
+
+	let (key, status) = set.next_exited().await.expect("one left in the set");
+	assert_eq!(key, "slow");
+	assert_eq!(status?.code(), Some(2));
+
+	assert!(set.is_empty());
+	assert!(set.next_exited().await.is_none(), "empty set");
+	Ok(())
+}
+
+#[tokio::test]
+async fn group_set_kill_handle_group() -> Result<()> {
+	let child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	let killer = child.kill_handle()?;
+	let mut set = GroupSet::new();
+	set.insert("yes", child);
+	assert!(
+		timeout(DIE_TIME, set.next_exited()).await.is_err(),
+		"still running"
+	);
+
+	killer.kill()?;
+	let (key, status) = set.next_exited().await.expect("one in the set");
+	assert_eq!(key, "yes");
+	assert_eq!(status?.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[tokio::test]
+async fn debug_group() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("exit 3")
+		.stdout(Stdio::piped())
+		.group_spawn()?;
+	let id = child.id().unwrap();
+	let debug = format!("{child:?}");
+	assert!(debug.contains(&format!("id: Some({id})")), "{debug}");
+	assert!(debug.contains("exit_status: None"), "{debug}");
+	assert!(debug.contains("stdout: true"), "{debug}");
+
+	child.wait().await?;
+	let debug = format!("{child:?}");
+	assert!(debug.contains("id: None"), "{debug}");
+	assert!(debug.contains("exit_status: Some"), "{debug}");
+	Ok(())
+}
+
+#[tokio::test]
+async fn kill_on_drop_group() -> Result<()> {
+	let child = Command::new("yes")
+		.stdout(Stdio::null())
+		.group()
+		.kill_on_drop(true)
+		.spawn()?;
+	let pgid = Pid::from_raw(child.id().unwrap() as _);
+	drop(child);
+
+	// Tokio reaps the dropped leader in the background, after which the group is gone
+	for _ in 0..10 {
+		if killpg(pgid, None) == Err(Errno::ESRCH) {
+			return Ok(());
+		}
+		sleep(DIE_TIME).await;
+	}
+	panic!("group still exists");
+}
+
+#[tokio::test]
+async fn detach_group() -> Result<()> {
+	let child = Command::new("yes")
+		.stdout(Stdio::null())
+		.group()
+		.kill_on_drop(true)
+		.spawn()?;
+	let pgid = Pid::from_raw(child.id().unwrap() as _);
+	child.detach();
+	sleep(DIE_TIME).await;
+	assert_eq!(killpg(pgid, None), Ok(()), "still running");
+
+	// Tokio reaps the dropped leader in the background
+	killpg(pgid, Signal::SIGKILL)?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn new_session_group() -> Result<()> {
+	let mut child = Command::new("yes")
+		.stdout(Stdio::null())
+		.group()
+		.new_session(true)
+		.spawn()?;
+	let pid = Pid::from_raw(child.id().unwrap() as _);
+	assert_eq!(getsid(Some(pid))?, pid, "session leader");
+	assert_eq!(getpgid(Some(pid))?, pid, "group leader");
+	child.kill().await?;
+	let status = child.wait().await?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
+#[tokio::test]
+async fn spawn_twice_group() -> Result<()> {
+	let mut command = Command::new("yes");
+	command.stdout(Stdio::null());
+	let mut builder = command.group();
+	builder.new_session(true);
+
+	let mut first = builder.spawn()?;
+	let first_pid = Pid::from_raw(first.id().unwrap() as _);
+	assert_eq!(getsid(Some(first_pid))?, first_pid, "session leader");
+
+	// options changed between spawns apply to the next one only
+	builder.new_session(false);
+	let mut second = builder.spawn()?;
+	let second_pid = Pid::from_raw(second.id().unwrap() as _);
+	assert_eq!(getpgid(Some(second_pid))?, second_pid, "group leader");
+	assert_ne!(
+		getsid(Some(second_pid))?,
+		second_pid,
+		"not a session leader"
+	);
+
+	for child in [&mut first, &mut second] {
+		child.kill().await?;
+		assert_eq!(child.wait().await?.signal(), Some(Signal::SIGKILL as i32));
+	}
+	Ok(())
+}
+
+#[tokio::test]
+async fn process_group_group() -> Result<()> {
+	let mut leader = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	let pgid = Pid::from_raw(leader.id().unwrap() as _);
+	let mut member = Command::new("yes")
+		.stdout(Stdio::null())
+		.group()
+		.process_group(pgid.as_raw())
+		.spawn()?;
+	assert_eq!(
+		getpgid(Some(Pid::from_raw(member.id().unwrap() as _)))?,
+		pgid
+	);
+	assert_eq!(member.group_id(), Some(pgid.as_raw() as u32));
+	assert_eq!(member.leader_id(), member.id());
+	assert_ne!(member.group_id(), member.leader_id());
+
+	// signalling the member hits the whole shared group
+	member.start_kill()?;
+	let status = member.wait().await?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	let status = leader.wait().await?;
+	assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+	assert_eq!(leader.group_id(), None, "exited");
+	Ok(())
+}
+
+#[tokio::test]
+async fn await_group() -> Result<()> {
+	let mut child = Command::new("sh").arg("-c").arg("exit 3").group_spawn()?;
+	let status = tokio::select! {
+		status = &mut child => status?,
+		_ = sleep(DIE_TIME * 10) => panic!("timed out"),
+	};
+	assert_eq!(status.code(), Some(3));
+	assert_eq!((&mut child).await?, status, "cached status");
+	Ok(())
+}
+
+#[tokio::test]
+async fn await_owned_group() -> Result<()> {
+	let child = Command::new("sh").arg("-c").arg("exit 3").group_spawn()?;
+	let status = tokio::spawn(child.into_future()).await??;
+	assert_eq!(status.code(), Some(3));
+
+	let child = Command::new("sh").arg("-c").arg("exit 4").group_spawn()?;
+	assert_eq!(child.await?.code(), Some(4));
+	Ok(())
+}
+
+#[tokio::test]
+async fn group_output_is_send_group() -> Result<()> {
+	let output = tokio::spawn(async {
+		Command::new("echo")
+			.arg("hello")
+			.stdout(Stdio::piped())
+			.group_output()
+			.await
+	})
+	.await??;
+	assert!(output.status.success());
+	assert_eq!(output.stdout, b"hello\n");
+	Ok(())
+}
+
+#[tokio::test]
+async fn kill_after_wait_group() -> Result<()> {
+	let mut child = Command::new("yes").stdout(Stdio::null()).group_spawn()?;
+	child.kill().await?;
+
+	let err = child.start_kill().expect_err("group already exited");
+	assert!(matches!(err, command_group::Error::AlreadyExited));
+	assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+	Ok(())
+}
+
+#[tokio::test]
+async fn signal_leader_group() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("trap 'exit 3' USR1; sleep 10 & wait")
+		.stdout(Stdio::null())
+		.group_spawn()?;
+	let pgid = Pid::from_raw(child.id().unwrap() as _);
+	sleep(DIE_TIME).await;
+	child.signal_leader(Signal::SIGUSR1)?;
+	let status = child.wait().await?;
+	assert_eq!(status.code(), Some(3));
+
+	// the rest of the group was left alone
+	assert_eq!(killpg(pgid, None), Ok(()));
+	killpg(pgid, Signal::SIGKILL)?;
+
+	assert!(
+		matches!(
+			child.signal_leader(Signal::SIGUSR1),
+			Err(command_group::Error::AlreadyExited)
+		),
+		"leader already reaped"
+	);
+	Ok(())
+}
+
+#[tokio::test]
+async fn process_count_group() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("sleep 10 & sleep 10 & wait")
+		.group_spawn()?;
+	sleep(DIE_TIME).await;
+	if cfg!(target_os = "linux") {
+		assert_eq!(child.process_count()?, 3);
+	}
+
+	child.kill().await?;
+	sleep(DIE_TIME).await;
+	if cfg!(target_os = "linux") {
+		assert_eq!(child.process_count()?, 0);
+	}
+	Ok(())
+}
+
+#[tokio::test]
+async fn merge_stderr_into_stdout_group() -> Result<()> {
+	let output = Command::new("sh")
+		.arg("-c")
+		.arg("echo out1; echo err1 >&2; echo out2")
+		.stdout(Stdio::piped())
+		.group()
+		.merge_stderr_into_stdout(true)
+		.spawn()?
+		.wait_with_output()
+		.await?;
+	assert!(output.status.success());
+	assert_eq!(output.stdout, b"out1\nerr1\nout2\n");
+	assert!(output.stderr.is_empty());
+	Ok(())
+}
+
+#[tokio::test]
+async fn wait_cancelled_group() -> Result<()> {
+	let mut child = Command::new("sh").arg("-c").arg("exit 3").group_spawn()?;
+	let pgid = child.id().unwrap() as i32;
+	let _member = Command::new("sleep")
+		.arg("1")
+		.group()
+		.process_group(pgid)
+		.spawn()?;
+
+	// the leader exits straight away, but the group lives on
+	assert!(
+		timeout(DIE_TIME * 3, child.wait()).await.is_err(),
+		"group still running"
+	);
+
+	let status = child.wait().await?;
+	assert_eq!(status.code(), Some(3));
+	Ok(())
+}
+
 #[tokio::test]
 async fn wait_after_die_normal() -> Result<()> {
 	let mut child = Command::new("echo").stdout(Stdio::null()).spawn()?;
@@ -241,6 +704,95 @@ async fn wait_group() -> Result<()> {
 	Ok(())
 }
 
+#[tokio::test]
+async fn stream_output_group() -> Result<()> {
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("printf 'one\\ntw'; sleep 0.1; printf 'o\\r\\n'; echo err >&2; printf end; exit 3")
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.group_spawn()?;
+
+	let mut lines = Vec::new();
+	let status = child.stream_output(|line| lines.push(line)).await?;
+	assert_eq!(status.code(), Some(3));
+
+	let from = |source| {
+		lines
+			.iter()
+			.filter(|line| line.source == source)
+			.map(|line| line.line.as_slice())
+			.collect::<Vec<_>>()
+	};
+	assert_eq!(from(OutputSource::Stdout), [&b"one"[..], b"two", b"end"]);
+	assert_eq!(from(OutputSource::Stderr), [b"err"]);
+	Ok(())
+}
+
+#[tokio::test]
+async fn communicate_group() -> Result<()> {
+	let input = vec![b'x'; 1_000_000];
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("cat; echo done >&2")
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.group_spawn()?;
+	let output = child.communicate(&input).await?;
+	assert!(output.status.success());
+	assert_eq!(output.stdout, input);
+	assert_eq!(output.stderr, b"done\n");
+	Ok(())
+}
+
+#[tokio::test]
+async fn wait_with_output_limited_group() -> Result<()> {
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("head -c 200000 /dev/zero >&2; echo hello; exit 3")
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.group_spawn()?;
+	let limited = child.wait_with_output_limited(100, 1_000).await?;
+	assert!(!limited.stdout_truncated, "stdout not truncated");
+	assert!(limited.stderr_truncated, "stderr truncated");
+	assert_eq!(limited.output.stdout, b"hello\n");
+	assert_eq!(limited.output.stderr.len(), 1_000);
+	assert_eq!(limited.output.status.code(), Some(3));
+	Ok(())
+}
+
+#[tokio::test]
+async fn wait_with_output_timeout_group() -> Result<()> {
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("echo hello; exit 3")
+		.stdout(Stdio::piped())
+		.group_spawn()?;
+	let timed = child.wait_with_output_timeout(DIE_TIME * 10).await?;
+	assert!(!timed.timed_out, "finished in time");
+	assert_eq!(timed.output.stdout, b"hello\n");
+	assert_eq!(timed.output.status.code(), Some(3));
+	Ok(())
+}
+
+#[tokio::test]
+async fn wait_with_output_timeout_killed_group() -> Result<()> {
+	let child = Command::new("sh")
+		.arg("-c")
+		.arg("echo partial; echo err >&2; sleep 10; echo never")
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.group_spawn()?;
+	let timed = child.wait_with_output_timeout(DIE_TIME * 2).await?;
+	assert!(timed.timed_out, "killed");
+	assert_eq!(timed.output.stdout, b"partial\n");
+	assert_eq!(timed.output.stderr, b"err\n");
+	assert_eq!(timed.output.status.signal(), Some(Signal::SIGKILL as i32));
+	Ok(())
+}
+
 #[tokio::test]
 async fn wait_with_output_normal() -> Result<()> {
 	let child = Command::new("echo")
@@ -306,3 +858,112 @@ async fn signal_group() -> Result<()> {
 
 	Ok(())
 }
+
+#[tokio::test]
+async fn spawn_erased_normal() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("exit 3")
+		.spawn_erased(false)?;
+	assert!(matches!(child, ErasedChild::Ungrouped(_)));
+	assert_eq!(child.wait().await?.code(), Some(3));
+	Ok(())
+}
+
+#[tokio::test]
+async fn spawn_erased_group() -> Result<()> {
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("exit 3")
+		.spawn_erased(true)?;
+	assert!(matches!(child, ErasedChild::Grouped(_)));
+	assert_eq!(child.wait().await?.code(), Some(3));
+	Ok(())
+}
+
+#[tokio::test]
+async fn erased_inner_read_stdout_normal() -> Result<()> {
+	let mut child: ErasedChild = Command::new("echo")
+		.arg("hello")
+		.stdout(Stdio::piped())
+		.spawn()?
+		.into();
+	assert_eq!(child.inner().id(), child.id());
+
+	let mut output = String::new();
+	if let Some(mut out) = child.inner().stdout.take() {
+		out.read_to_string(&mut output).await?;
+	}
+
+	assert_eq!(output.as_str(), "hello\n");
+	child.into_inner().wait().await?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn erased_inner_read_stdout_group() -> Result<()> {
+	let mut child: ErasedChild = Command::new("echo")
+		.arg("hello")
+		.stdout(Stdio::piped())
+		.group_spawn()?
+		.into();
+	assert_eq!(child.inner().id(), child.id());
+
+	let mut output = String::new();
+	if let Some(mut out) = child.inner().stdout.take() {
+		out.read_to_string(&mut output).await?;
+	}
+
+	assert_eq!(output.as_str(), "hello\n");
+	child.into_inner().wait().await?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn output_lines_group() -> Result<()> {
+	use tokio_stream::StreamExt;
+
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("echo out1; echo err1 >&2; echo out2")
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.group_spawn()?;
+
+	let lines: Vec<_> = child.output_lines().collect::<Result<_>>().await?;
+	let from = |source| {
+		lines
+			.iter()
+			.filter(|(s, _)| *s == source)
+			.map(|(_, line)| line.as_str())
+			.collect::<Vec<_>>()
+	};
+	assert_eq!(from(OutputSource::Stdout), ["out1", "out2"]);
+	assert_eq!(from(OutputSource::Stderr), ["err1"]);
+
+	assert!(child.wait().await?.success());
+	Ok(())
+}
+
+#[tokio::test]
+async fn output_lines_bytes_group() -> Result<()> {
+	use tokio_stream::StreamExt;
+
+	let mut child = Command::new("sh")
+		.arg("-c")
+		.arg("printf 'a\\377\\r\\nb'")
+		.stdout(Stdio::piped())
+		.group_spawn()?;
+
+	let lines: Vec<_> = child.output_lines_bytes().collect::<Result<_>>().await?;
+	assert_eq!(
+		lines,
+		[
+			(OutputSource::Stdout, b"a\xff".to_vec()),
+			(OutputSource::Stdout, b"b".to_vec())
+		]
+	);
+
+	assert!(child.wait().await?.success());
+	Ok(())
+}
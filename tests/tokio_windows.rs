@@ -1,7 +1,11 @@
 #![cfg(all(windows, feature = "with-tokio"))]
 
-use command_group::AsyncCommandGroup;
-use std::{io::Result, process::Stdio, time::Duration};
+use command_group::{AsyncCommandGroup, ControlEvent};
+use std::{
+	io::{ErrorKind, Result},
+	process::Stdio,
+	time::Duration,
+};
 use tokio::{
 	io::{AsyncReadExt, AsyncWriteExt},
 	process::Command,
@@ -233,6 +237,23 @@ async fn wait_with_output_group() -> Result<()> {
 	Ok(())
 }
 
+#[tokio::test]
+async fn wait_with_output_large_both_group() -> Result<()> {
+	// stderr is written first, so reading stdout to the end first would deadlock
+	let child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("[Console]::Error.Write('e' * 1000000); [Console]::Out.Write('o' * 1000000)")
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.group_spawn()?;
+
+	let output = child.wait_with_output().await?;
+	assert!(output.status.success());
+	assert_eq!(output.stdout, vec![b'o'; 1_000_000]);
+	assert_eq!(output.stderr, vec![b'e'; 1_000_000]);
+	Ok(())
+}
+
 #[tokio::test]
 async fn id_same_as_inner_group() -> Result<()> {
 	let mut child = Command::new("powershell.exe")
@@ -240,5 +261,136 @@ async fn id_same_as_inner_group() -> Result<()> {
 		.arg("echo hello")
 		.group_spawn()?;
 	assert_eq!(child.id(), child.inner().id());
+	assert_eq!(child.group_id(), child.id());
+	assert_eq!(child.leader_id(), child.id());
+	Ok(())
+}
+
+#[tokio::test]
+async fn job_accounting_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("echo hello")
+		.stdout(Stdio::null())
+		.group_spawn()?;
+	child.wait().await?;
+	let accounting = child.job_accounting()?;
+	assert!(accounting.total_processes >= 1, "counted the leader");
+	assert!(accounting.peak_memory > 0, "used some memory");
+	Ok(())
+}
+
+#[tokio::test]
+async fn send_control_group() -> Result<()> {
+	let mut child = Command::new("ping")
+		.args(["-t", "localhost"])
+		.stdout(Stdio::null())
+		.group()
+		.new_process_group(true)
+		.spawn()?;
+	child.send_control(ControlEvent::CtrlBreak)?;
+	child.wait().await?;
+
+	let err = child.send_control(ControlEvent::CtrlBreak).unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	Ok(())
+}
+
+#[tokio::test]
+async fn merge_stderr_into_stdout_group() -> Result<()> {
+	let output = Command::new("cmd")
+		.arg("/C")
+		.arg("echo out&& (echo err)1>&2")
+		.group()
+		.merge_stderr_into_stdout(true)
+		.spawn()?
+		.wait_with_output()
+		.await?;
+	assert!(output.status.success());
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	assert_eq!(
+		stdout.lines().map(str::trim).collect::<Vec<_>>(),
+		["out", "err"]
+	);
+	assert!(output.stderr.is_empty());
+	Ok(())
+}
+
+#[tokio::test]
+async fn start_kill_with_code_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	child.start_kill_with_code(42)?;
+	let status = child.wait().await?;
+	assert_eq!(status.code(), Some(42));
+	Ok(())
+}
+
+#[tokio::test]
+async fn start_kill_with_code_then_try_wait_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	child.start_kill_with_code(137)?;
+	sleep(DIE_TIME).await;
+	let status = child.try_wait()?.expect("killed");
+	assert_eq!(status.code(), Some(137));
+	assert_eq!(child.wait().await?.code(), Some(137));
+	Ok(())
+}
+
+#[tokio::test]
+async fn process_count_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	assert!(child.process_count()? >= 1, "leader is running");
+	child.kill().await?;
+	child.wait().await?;
+	assert_eq!(child.process_count()?, 0);
+	Ok(())
+}
+
+#[tokio::test]
+async fn wait_for_members_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("Start-Process -NoNewWindow powershell.exe -ArgumentList '/C','Start-Sleep 1'")
+		.group_spawn()?;
+	assert!(child.wait().await?.success());
+	assert_eq!(child.process_count()?, 0);
+	Ok(())
+}
+
+#[tokio::test]
+async fn try_wait_after_grandchild_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("Start-Process -NoNewWindow -Wait cmd.exe -ArgumentList '/C','exit'; exit 3")
+		.group_spawn()?;
+	sleep(DIE_TIME).await;
+
+	let status = child.try_wait()?.expect("exited");
+	assert_eq!(status.code(), Some(3));
+	assert_eq!(child.process_count()?, 0);
+	assert_eq!(child.wait().await?.code(), Some(3));
+	Ok(())
+}
+
+#[tokio::test]
+async fn kill_after_wait_group() -> Result<()> {
+	let mut child = Command::new("powershell.exe")
+		.arg("/C")
+		.arg("pause")
+		.group_spawn()?;
+	child.kill().await?;
+
+	let err = child.start_kill().expect_err("group already exited");
+	assert!(matches!(err, command_group::Error::AlreadyExited));
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
 	Ok(())
 }